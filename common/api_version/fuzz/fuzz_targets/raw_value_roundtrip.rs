@@ -0,0 +1,16 @@
+#![no_main]
+
+use api_version::{ApiV1, KvFormat, RawValue};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|value: RawValue<Vec<u8>>| {
+    let encoded = ApiV1::encode_raw_value_owned(value.clone());
+    let decoded = ApiV1::decode_raw_value(&encoded).unwrap();
+    assert_eq!(decoded.user_value, value.user_value.as_slice());
+    assert_eq!(decoded.ts, value.ts);
+    assert_eq!(decoded.status, value.status);
+    assert_eq!(decoded.tombstone, value.tombstone);
+    assert_eq!(decoded.value_type, value.value_type);
+    assert_eq!(decoded.refcount, value.refcount);
+    assert_eq!(decoded.deleted_size, value.deleted_size);
+});
@@ -0,0 +1,117 @@
+use crate::{ApiV1, KvFormat, RawValue, StatusCode};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+const HEADER_SIZE: usize = number::U64_SIZE + number::U64_SIZE;
+
+/// `ApiV1` fields laid out for append-heavy columns: a `ts || status` header,
+/// followed by the optional fields, in front of the user value instead of a
+/// trailer.
+///
+/// The ts is stored descending, so among versions of a key the newest has
+/// the smallest header, and reading it never touches the payload.
+#[derive(Default, Clone, Copy)]
+pub struct ApiV1Append;
+
+impl ApiV1Append {
+    /// Reads the ts from the header only.
+    pub fn peek_ts(bytes: &[u8]) -> Option<u64> {
+        let mut ts_slice = bytes.get(..number::U64_SIZE)?;
+        let ts = number::decode_u64(&mut ts_slice).ok()?;
+        Some(!ts)
+    }
+}
+
+impl KvFormat for ApiV1Append {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        if bytes.len() < HEADER_SIZE {
+            return None;
+        }
+        let ts = Self::peek_ts(bytes)?;
+        let mut status_slice = &bytes[number::U64_SIZE..HEADER_SIZE];
+        let status = StatusCode::from(number::decode_u64(&mut status_slice).ok()?);
+
+        let optional_end = HEADER_SIZE + ApiV1::optional_fields_len(status);
+        let block = bytes.get(HEADER_SIZE..optional_end)?;
+        let optional = ApiV1::decode_optional_fields(block, status).ok()?;
+
+        Some(RawValue {
+            user_value: &bytes[optional_end..],
+            ts: Some(ts),
+            status,
+            tombstone: status.is_tombstone(),
+            value_type: optional.value_type,
+            refcount: optional.refcount,
+            deleted_size: optional.deleted_size,
+        })
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_SIZE + 1 + value.user_value.len());
+
+        buf.encode_u64(!value.ts.unwrap_or_default()).unwrap();
+        buf.encode_u64(value.encoded_status().bits()).unwrap();
+        ApiV1::encode_optional_fields(&mut buf, &value);
+        buf.extend_from_slice(value.user_value);
+
+        buf
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        Self::encode_raw_value(value.as_borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ValueType;
+
+    fn value(user_value: &[u8], ts: u64) -> RawValue<&[u8]> {
+        RawValue::new(user_value, Some(ts), StatusCode::from_user_status(1))
+    }
+
+    #[test]
+    fn append_works() {
+        let v = value(b"123", 1654045749000);
+        let b = ApiV1Append::encode_raw_value(v);
+        assert_eq!(&b[HEADER_SIZE..], b"123");
+        assert_eq!(ApiV1Append::decode_raw_value(&b), Some(v));
+
+        let json = RawValue {
+            value_type: ValueType::Json,
+            ..v
+        };
+        let b = ApiV1Append::encode_raw_value(json);
+        assert_eq!(&b[HEADER_SIZE + 1..], b"123");
+        let v1 = ApiV1Append::decode_raw_value(&b).unwrap();
+        assert_eq!(v1.value_type, ValueType::Json);
+        assert_eq!(v1.user_value, b"123");
+        assert_eq!(ApiV1Append::decode_raw_value(&b[..HEADER_SIZE]), None);
+
+        let tombstone = ApiV1Append::encode_raw_value_owned(RawValue {
+            tombstone: true,
+            ..RawValue::new(vec![], None, StatusCode::GOOD)
+        });
+        let t = ApiV1Append::decode_raw_value(&tombstone).unwrap();
+        assert!(t.tombstone);
+        assert_eq!(t.ts, Some(0));
+        assert_eq!(ApiV1Append::decode_raw_value(&tombstone[..15]), None);
+    }
+
+    #[test]
+    fn append_newest_from_header() {
+        let payload = vec![0xab; 1 << 16];
+        let versions: Vec<Vec<u8>> = [5, 300, 7]
+            .iter()
+            .map(|&ts| ApiV1Append::encode_raw_value(value(&payload, ts)))
+            .collect();
+
+        // Only the first 8 bytes are needed.
+        let headers: Vec<&[u8]> = versions.iter().map(|v| &v[..number::U64_SIZE]).collect();
+        let newest = headers.iter().min().unwrap();
+        assert_eq!(ApiV1Append::peek_ts(newest), Some(300));
+        assert_eq!(ApiV1Append::peek_ts(&versions[2]), Some(7));
+        assert_eq!(ApiV1Append::peek_ts(&versions[2][..7]), None);
+    }
+}
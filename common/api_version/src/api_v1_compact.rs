@@ -0,0 +1,207 @@
+use crate::{api_v1_magic, ApiV1, ApiV1Magic, DecodeError, KvFormat, RawValue, StatusCode};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+/// Version 2 of the `ApiV1Magic` format, with the status stored as a varint
+/// so the common `GOOD` status takes 1 byte instead of 8.
+///
+/// The body is `user_value || optional fields || ts || status`, where the status is rotated to
+/// put the system bits lowest, so a tombstone still fits in 2 bytes, and its
+/// varint bytes are written back to front to be read from the end.
+#[derive(Default, Clone, Copy)]
+pub struct ApiV1Compact;
+
+impl ApiV1Compact {
+    pub const VERSION: u8 = 2;
+
+    /// Decodes the body following a version 2 header, reporting offsets
+    /// into `body`.
+    pub(crate) fn decode_body(body: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        let eof = |offset| DecodeError::UnexpectedEOF { offset };
+        let mut status_bytes = [0; number::MAX_VAR_U64_LEN];
+        let n = body.len().min(number::MAX_VAR_U64_LEN);
+        for (dst, src) in status_bytes.iter_mut().zip(body.iter().rev().take(n)) {
+            *dst = *src;
+        }
+        let mut status_slice = &status_bytes[..n];
+        let s = number::decode_var_u64(&mut status_slice).map_err(|_| eof(body.len()))?;
+        let status = StatusCode::from(s.rotate_right(8));
+
+        let rest_len = body.len() - (n - status_slice.len());
+        let ts_start = rest_len
+            .checked_sub(number::U64_SIZE)
+            .ok_or_else(|| eof(rest_len))?;
+        let mut ts_slice = &body[ts_start..rest_len];
+        let ts = number::decode_u64(&mut ts_slice).map_err(|_| eof(rest_len))?;
+        let (user_value, optional) = ApiV1::split_optional_fields(&body[..ts_start], status)?;
+
+        Ok(RawValue {
+            user_value,
+            ts: Some(ts),
+            status,
+            tombstone: status.is_tombstone(),
+            value_type: optional.value_type,
+            refcount: optional.refcount,
+            deleted_size: optional.deleted_size,
+        })
+    }
+}
+
+impl KvFormat for ApiV1Compact {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        ApiV1Magic::decode_raw_value(bytes)
+    }
+
+    fn try_decode_raw_value(bytes: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        ApiV1Magic::try_decode_raw_value(bytes)
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            api_v1_magic::HEADER_SIZE + value.user_value.len() + number::U64_SIZE + 1,
+        );
+
+        buf.extend_from_slice(&ApiV1Magic::MAGIC);
+        buf.push(Self::VERSION);
+        buf.extend_from_slice(value.user_value);
+        ApiV1::encode_optional_fields(&mut buf, &value);
+        buf.encode_u64(value.ts.unwrap_or_default()).unwrap();
+
+        let status_start = buf.len();
+        buf.encode_var_u64(value.encoded_status().bits().rotate_left(8))
+            .unwrap();
+        buf[status_start..].reverse();
+
+        buf
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        Self::encode_raw_value(value.as_borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ValueType;
+
+    fn value(status: StatusCode, tombstone: bool) -> RawValue<&'static [u8]> {
+        RawValue {
+            tombstone,
+            ..RawValue::new(b"123", Some(1654045749000), status)
+        }
+    }
+
+    #[test]
+    fn compact_good_status() {
+        let v = value(StatusCode::GOOD, false);
+        let b = ApiV1Compact::encode_raw_value(v);
+        assert_eq!(
+            b.len(),
+            api_v1_magic::HEADER_SIZE + 3 + number::U64_SIZE + 1
+        );
+        assert_eq!(b.len() + 7, ApiV1Magic::encode_raw_value(v).len());
+        assert_eq!(ApiV1Compact::decode_raw_value(&b), Some(v));
+
+        let empty = ApiV1Compact::encode_raw_value(RawValue {
+            user_value: &b""[..],
+            ts: None,
+            ..v
+        });
+        assert_eq!(empty, &[0xCE, 0x11, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn compact_large_status() {
+        let statuses = [
+            StatusCode::IS_TOMBSTONE,
+            StatusCode::from_user_status(1),
+            StatusCode::from_user_status(u64::MAX),
+            // The `HAS_*` flags are implied by the optional fields.
+            StatusCode::from(u64::MAX)
+                - StatusCode::HAS_TYPE
+                - StatusCode::HAS_REFCOUNT
+                - StatusCode::HAS_DELETED_SIZE,
+        ];
+        for status in statuses {
+            let v = value(status, status.is_tombstone());
+            let b = ApiV1Compact::encode_raw_value(v);
+            assert_eq!(ApiV1Compact::decode_raw_value(&b), Some(v));
+            assert_eq!(ApiV1Magic::decode_raw_value(&b), Some(v));
+        }
+
+        let counter = RawValue {
+            value_type: ValueType::Counter,
+            ..value(StatusCode::GOOD, false)
+        };
+        let b = ApiV1Compact::encode_raw_value(counter);
+        let v1 = ApiV1Compact::decode_raw_value(&b).unwrap();
+        assert_eq!(v1.value_type, ValueType::Counter);
+        assert_eq!(v1.user_value, counter.user_value);
+
+        let tombstone = ApiV1Compact::encode_raw_value(value(StatusCode::GOOD, true));
+        assert_eq!(
+            tombstone.len(),
+            api_v1_magic::HEADER_SIZE + 3 + number::U64_SIZE + 2
+        );
+        assert!(
+            ApiV1Compact::decode_raw_value(&tombstone)
+                .unwrap()
+                .tombstone
+        );
+    }
+
+    #[test]
+    fn compact_version_gate() {
+        let v = value(StatusCode::from_user_status(9), false);
+        let v1 = ApiV1Magic::encode_raw_value(v);
+        assert_eq!(ApiV1Compact::decode_raw_value(&v1), Some(v));
+
+        let mut b = ApiV1Compact::encode_raw_value(v);
+        assert_eq!(b[2], ApiV1Compact::VERSION);
+        assert!(ApiV1Compact::decode_raw_value(&b[..api_v1_magic::HEADER_SIZE + 4]).is_none());
+        assert!(ApiV1Compact::decode_raw_value(&b[..api_v1_magic::HEADER_SIZE]).is_none());
+        b[2] = ApiV1Magic::VERSION;
+        assert_ne!(ApiV1Magic::decode_raw_value(&b), Some(v));
+    }
+
+    #[test]
+    fn compact_error_offsets() {
+        use api_v1_magic::HEADER_SIZE;
+
+        // A status varint that never ends.
+        let mut b = ApiV1Compact::encode_raw_value(value(StatusCode::GOOD, false));
+        b.truncate(HEADER_SIZE);
+        b.extend_from_slice(&[0x80; 3]);
+        assert_eq!(
+            ApiV1Compact::try_decode_raw_value(&b),
+            Err(DecodeError::UnexpectedEOF { offset: b.len() })
+        );
+
+        // Half of the refcount is missing from the optional block in front
+        // of the ts.
+        let mut b = ApiV1Compact::encode_raw_value(RawValue {
+            user_value: &b""[..],
+            refcount: Some(5),
+            ..value(StatusCode::GOOD, false)
+        });
+        b.drain(HEADER_SIZE..HEADER_SIZE + 2);
+        assert_eq!(
+            ApiV1Compact::try_decode_raw_value(&b),
+            Err(DecodeError::UnexpectedEOF {
+                offset: HEADER_SIZE + 2
+            })
+        );
+
+        // Nothing in front of the status for the ts.
+        let b = ApiV1Compact::encode_raw_value(value(StatusCode::GOOD, false));
+        let status = &b[b.len() - 1..];
+        let b = [&b[..HEADER_SIZE + 5], status].concat();
+        assert_eq!(
+            ApiV1Compact::try_decode_raw_value(&b),
+            Err(DecodeError::UnexpectedEOF {
+                offset: HEADER_SIZE + 5
+            })
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{ApiV1, ApiV1Compact, DecodeError, KvFormat, RawValue};
+use cells_utils::codec::number;
+
+const MAGIC: [u8; 2] = ApiV1Magic::MAGIC;
+const VERSION: u8 = ApiV1Magic::VERSION;
+pub(crate) const HEADER_SIZE: usize = MAGIC.len() + 1;
+
+/// `ApiV1` values prefixed with `[MAGIC, VERSION]`, so foreign or
+/// future-format buffers are rejected up front.
+///
+/// Decoding also accepts the `ApiV1Compact` version.
+#[derive(Default, Clone, Copy)]
+pub struct ApiV1Magic;
+
+impl ApiV1Magic {
+    /// Leading bytes identifying a cells value buffer.
+    pub const MAGIC: [u8; 2] = [0xCE, 0x11];
+    /// The value format version written after the magic.
+    pub const VERSION: u8 = 1;
+}
+
+impl KvFormat for ApiV1Magic {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        Self::try_decode_raw_value(bytes).ok()
+    }
+
+    fn try_decode_raw_value(bytes: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        if bytes.len() < HEADER_SIZE {
+            let offset = if bytes.len() < MAGIC.len() {
+                0
+            } else {
+                MAGIC.len()
+            };
+            return Err(DecodeError::UnexpectedEOF { offset });
+        }
+        let (header, body) = bytes.split_at(HEADER_SIZE);
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError::BadMagic {
+                magic: [header[0], header[1]],
+                offset: 0,
+            });
+        }
+        let value = match header[MAGIC.len()] {
+            VERSION => ApiV1::try_decode_raw_value(body),
+            ApiV1Compact::VERSION => ApiV1Compact::decode_body(body),
+            version => {
+                return Err(DecodeError::UnsupportedVersion {
+                    version,
+                    offset: MAGIC.len(),
+                })
+            }
+        };
+
+        value.map_err(|e| e.offset_by(HEADER_SIZE))
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            HEADER_SIZE + value.user_value.len() + number::U64_SIZE + number::U64_SIZE,
+        );
+
+        buf.extend_from_slice(&MAGIC);
+        buf.push(VERSION);
+        buf.extend_from_slice(value.user_value);
+        ApiV1::encode_trailer(&mut buf, &value);
+
+        buf
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        Self::encode_raw_value(value.as_borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::StatusCode;
+
+    #[test]
+    fn magic_works() {
+        let v = RawValue::new(
+            &b"123"[..],
+            Some(1654045749000),
+            StatusCode::from_user_status(7),
+        );
+
+        let b = ApiV1Magic::encode_raw_value(v);
+        assert_eq!(&b[..3], &[0xCE, 0x11, VERSION]);
+
+        let v1 = ApiV1Magic::try_decode_raw_value(&b).unwrap();
+        assert_eq!(v1, v);
+        assert_eq!(&b[3..], ApiV1::encode_raw_value(v).as_slice());
+    }
+
+    #[test]
+    fn magic_rejects_foreign_data() {
+        let mut b = ApiV1Magic::encode_raw_value_owned(RawValue::new(
+            b"123".to_vec(),
+            None,
+            StatusCode::GOOD,
+        ));
+
+        let mut foreign = b.clone();
+        foreign[0] = 0xAB;
+        assert_eq!(
+            ApiV1Magic::try_decode_raw_value(&foreign),
+            Err(DecodeError::BadMagic {
+                magic: [0xAB, 0x11],
+                offset: 0
+            })
+        );
+        assert!(ApiV1Magic::decode_raw_value(&foreign).is_none());
+
+        // A plain ApiV1 value has no header.
+        let plain = ApiV1::encode_raw_value(ApiV1Magic::decode_raw_value(&b).unwrap());
+        assert!(ApiV1Magic::try_decode_raw_value(&plain).is_err());
+
+        b[2] = 0xff;
+        assert_eq!(
+            ApiV1Magic::try_decode_raw_value(&b),
+            Err(DecodeError::UnsupportedVersion {
+                version: 0xff,
+                offset: 2
+            })
+        );
+        assert_eq!(
+            ApiV1Magic::try_decode_raw_value(&[0xCE]),
+            Err(DecodeError::UnexpectedEOF { offset: 0 })
+        );
+        assert_eq!(
+            ApiV1Magic::try_decode_raw_value(&MAGIC),
+            Err(DecodeError::UnexpectedEOF { offset: 2 })
+        );
+
+        // Truncated inside the body, past the header: the status is read
+        // from the last 8 bytes, and the ts that should end where the
+        // status starts is missing.
+        b[2] = ApiV1Magic::VERSION;
+        let truncated = &b[..HEADER_SIZE + 12];
+        let err = ApiV1Magic::try_decode_raw_value(truncated).unwrap_err();
+        assert_eq!(err, DecodeError::UnexpectedEOF { offset: 7 });
+        assert_eq!(err.to_string(), "Decode Error: Unexpected eof at offset 7");
+        assert_ne!(err.offset(), truncated.len());
+    }
+}
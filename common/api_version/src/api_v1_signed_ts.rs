@@ -0,0 +1,76 @@
+use crate::{ApiV1, KvFormat, RawValue};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+/// `ApiV1` layout with the ts stored through the comparable i64 encoder, for
+/// data that carries pre-epoch (negative) timestamps.
+///
+/// `RawValue::ts` holds the two's complement bits of the signed ts, read it
+/// back with `RawValue::ts_signed`.
+#[derive(Default, Clone, Copy)]
+pub struct ApiV1SignedTs;
+
+impl KvFormat for ApiV1SignedTs {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        let mut value = ApiV1::decode_raw_value(bytes)?;
+        let ts_bytes = ApiV1::raw_ts_bytes(bytes)?;
+        let ts = number::decode_i64(&mut &ts_bytes[..]).ok()?;
+        value.ts = Some(ts as u64);
+        Some(value)
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        let mut buf = ApiV1::encode_raw_value(value);
+        let ts_start = buf.len() - number::U64_SIZE - number::U64_SIZE;
+        let mut ts_slot = &mut buf[ts_start..ts_start + number::U64_SIZE];
+        ts_slot
+            .encode_i64(value.ts.unwrap_or_default() as i64)
+            .unwrap();
+        buf
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        Self::encode_raw_value(value.as_borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::StatusCode;
+
+    fn value(ts: i64) -> RawValue<&'static [u8]> {
+        RawValue::new(b"123", Some(ts as u64), StatusCode::GOOD)
+    }
+
+    #[test]
+    fn signed_ts_works() {
+        let ts = -86_400_000;
+        let b = ApiV1SignedTs::encode_raw_value(value(ts));
+        let v = ApiV1SignedTs::decode_raw_value(&b).unwrap();
+        assert_eq!(v.ts_signed(), Some(ts));
+        assert_eq!(v.user_value, b"123");
+
+        // Pre-epoch values sort before post-epoch ones.
+        let later = ApiV1SignedTs::encode_raw_value(value(1));
+        assert!(ApiV1::raw_ts_bytes(&b) < ApiV1::raw_ts_bytes(&later));
+    }
+
+    #[test]
+    fn signed_ts_agrees_for_positive() {
+        for ts in [0, 1, 1654045749000, i64::MAX] {
+            let signed = ApiV1SignedTs::encode_raw_value(value(ts));
+            let plain = ApiV1::encode_raw_value(value(ts));
+            assert_eq!(
+                ApiV1SignedTs::decode_raw_value(&signed).unwrap().ts,
+                ApiV1::decode_raw_value(&plain).unwrap().ts
+            );
+            // Only the ts field differs, by its sign bit.
+            assert_ne!(signed, plain);
+            let ts_start = plain.len() - 2 * number::U64_SIZE;
+            assert_eq!(signed[..ts_start], plain[..ts_start]);
+            assert_eq!(signed[ts_start] ^ plain[ts_start], 0x80);
+            assert_eq!(signed[ts_start + 1..], plain[ts_start + 1..]);
+        }
+    }
+}
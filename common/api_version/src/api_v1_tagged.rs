@@ -0,0 +1,111 @@
+use crate::{ApiV1, KvFormat, RawValue, StatusCode};
+
+/// `ApiV1` values with a trailing marker byte, so that plain payloads with a
+/// `GOOD` status and no ts skip the ts and status trailer entirely.
+///
+/// The body is either `user_value || PLAIN` or an `ApiV1` value followed by
+/// `FULL`.
+#[derive(Default, Clone, Copy)]
+pub struct ApiV1Tagged;
+
+impl ApiV1Tagged {
+    /// Marks a bare user value.
+    pub const PLAIN: u8 = 0;
+    /// Marks an `ApiV1` encoded value.
+    pub const FULL: u8 = 1;
+
+    /// Whether `value` is stored without a trailer.
+    pub fn is_plain<T: AsRef<[u8]>>(value: &RawValue<T>) -> bool {
+        value.ts.is_none() && value.encoded_status() == StatusCode::GOOD
+    }
+}
+
+impl KvFormat for ApiV1Tagged {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        let (&marker, body) = bytes.split_last()?;
+        match marker {
+            Self::PLAIN => Some(RawValue::new(body, None, StatusCode::GOOD)),
+            Self::FULL => ApiV1::decode_raw_value(body),
+            _ => None,
+        }
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        if Self::is_plain(&value) {
+            let mut buf = Vec::with_capacity(value.user_value.len() + 1);
+            buf.extend_from_slice(value.user_value);
+            buf.push(Self::PLAIN);
+            return buf;
+        }
+
+        let mut buf = Vec::with_capacity(value.encoded_len() + 1);
+        buf.extend_from_slice(value.user_value);
+        ApiV1::encode_trailer(&mut buf, &value);
+        buf.push(Self::FULL);
+        buf
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        Self::encode_raw_value(value.as_borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ValueType;
+
+    #[test]
+    fn tagged_plain() {
+        for user_value in [&b""[..], b"abc", &[0xff; 64]] {
+            let v = RawValue::new(user_value, None, StatusCode::GOOD);
+            let b = ApiV1Tagged::encode_raw_value(v);
+            assert_eq!(b.len() + 15, ApiV1::encode_raw_value(v).len());
+            assert_eq!(*b.last().unwrap(), ApiV1Tagged::PLAIN);
+            assert_eq!(ApiV1Tagged::decode_raw_value(&b).unwrap(), v);
+
+            // The full form of the same value decodes the same, bar the
+            // ts `ApiV1` always writes.
+            let mut full = ApiV1::encode_raw_value(v);
+            full.push(ApiV1Tagged::FULL);
+            let decoded = ApiV1Tagged::decode_raw_value(&full).unwrap();
+            assert_eq!(
+                RawValue {
+                    ts: None,
+                    ..decoded
+                },
+                v
+            );
+        }
+    }
+
+    #[test]
+    fn tagged_full() {
+        let cases = [
+            RawValue::new(&b"abc"[..], Some(0), StatusCode::GOOD),
+            RawValue::new(&b"abc"[..], None, StatusCode::from_user_status(1)),
+            RawValue::new(&b"abc"[..], None, StatusCode::IS_TOMBSTONE),
+            RawValue {
+                value_type: ValueType::Json,
+                ..RawValue::new(&b"{}"[..], None, StatusCode::GOOD)
+            },
+            RawValue {
+                refcount: Some(1),
+                ..RawValue::new(&b"abc"[..], None, StatusCode::GOOD)
+            },
+        ];
+        for v in cases {
+            let b = ApiV1Tagged::encode_raw_value(v);
+            assert_eq!(*b.last().unwrap(), ApiV1Tagged::FULL);
+            assert_eq!(b.len(), v.encoded_len() + 1);
+            let decoded = ApiV1Tagged::decode_raw_value(&b).unwrap();
+            assert_eq!(decoded, ApiV1::decode_raw_value(&b[..b.len() - 1]).unwrap());
+            assert_eq!(decoded.user_value, v.user_value);
+        }
+
+        assert_eq!(ApiV1Tagged::decode_raw_value(&[]), None);
+        assert_eq!(ApiV1Tagged::decode_raw_value(b"abc\x02"), None);
+        assert_eq!(ApiV1Tagged::decode_raw_value(b"abc\x01"), None);
+    }
+}
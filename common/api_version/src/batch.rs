@@ -0,0 +1,92 @@
+use crate::{DecodeError, KvFormat, RawValue};
+
+/// The total length of `values` encoded with `ApiV1`, to allocate a batch
+/// buffer once.
+pub fn batch_encoded_len(values: &[RawValue<&[u8]>]) -> usize {
+    values.iter().map(RawValue::encoded_len).sum()
+}
+
+/// Decodes every entry of a batch with `F` independently, so that one corrupt
+/// entry doesn't hide the others.
+pub fn decode_batch_collect<'a, F: KvFormat>(
+    entries: &[&'a [u8]],
+) -> Vec<Result<RawValue<&'a [u8]>, DecodeError>> {
+    entries
+        .iter()
+        .map(|entry| F::try_decode_raw_value(entry))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use cells_utils::codec::number;
+
+    use super::*;
+    use crate::{ApiV1, StatusCode, ValueType};
+
+    #[test]
+    fn batch_len() {
+        let values = [
+            RawValue::new(&b""[..], None, StatusCode::GOOD),
+            RawValue::new(&b"abc"[..], Some(1), StatusCode::IS_TOMBSTONE),
+            RawValue {
+                value_type: ValueType::Json,
+                ..RawValue::new(&b"{}"[..], Some(2), StatusCode::from_user_status(3))
+            },
+        ];
+        let mut encoded = vec![];
+        for v in values {
+            encoded.extend(ApiV1::encode_raw_value(v));
+        }
+        assert_eq!(batch_encoded_len(&values), encoded.len());
+        for v in values {
+            assert_eq!(v.encoded_len(), ApiV1::encode_raw_value(v).len());
+        }
+        assert_eq!(batch_encoded_len(&[]), 0);
+    }
+
+    #[test]
+    fn batch_collect() {
+        let values: Vec<_> = (0..5u8)
+            .map(|i| {
+                RawValue::new(
+                    vec![i; i as usize],
+                    Some(i as u64),
+                    StatusCode::from_user_status(i as u64),
+                )
+            })
+            .collect();
+        let encoded: Vec<_> = values
+            .iter()
+            .map(|v| ApiV1::encode_raw_value_owned(v.clone()))
+            .collect();
+
+        // Corrupt the first, a middle and the last entry.
+        let mut entries: Vec<&[u8]> = encoded.iter().map(|e| e.as_slice()).collect();
+        entries[0] = &entries[0][..3];
+        entries[2] = &[];
+        entries[4] = &entries[4][..15];
+
+        let results = decode_batch_collect::<ApiV1>(&entries);
+        assert_eq!(results.len(), entries.len());
+        for (i, result) in results.iter().enumerate() {
+            // The status is missing from the first two, the ts in front of
+            // it from the last.
+            let eof = |offset| Err(DecodeError::UnexpectedEOF { offset });
+            match i {
+                0 => assert_eq!(*result, eof(3)),
+                2 => assert_eq!(*result, eof(0)),
+                4 => assert_eq!(*result, eof(15 - number::U64_SIZE)),
+                _ => {
+                    let v = result.as_ref().unwrap();
+                    assert_eq!(v.user_value, values[i].user_value.as_slice());
+                    assert_eq!(v.ts, values[i].ts);
+                    assert_eq!(v.status, values[i].status);
+                }
+            }
+        }
+
+        assert!(decode_batch_collect::<ApiV1>(&[]).is_empty());
+    }
+}
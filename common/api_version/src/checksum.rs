@@ -0,0 +1,149 @@
+use std::hash::Hasher;
+
+use twox_hash::XxHash64;
+
+/// Checksum algorithms an entry can be protected with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumKind {
+    Crc32c,
+    XxHash64,
+}
+
+impl ChecksumKind {
+    /// The tag byte identifying the algorithm in an encoded entry.
+    pub fn tag(self) -> u8 {
+        match self {
+            ChecksumKind::Crc32c => 1,
+            ChecksumKind::XxHash64 => 2,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<ChecksumKind> {
+        match tag {
+            1 => Some(ChecksumKind::Crc32c),
+            2 => Some(ChecksumKind::XxHash64),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes of the stored checksum.
+    pub fn size(self) -> usize {
+        match self {
+            ChecksumKind::Crc32c => 4,
+            ChecksumKind::XxHash64 => 8,
+        }
+    }
+
+    /// Computes the checksum over the concatenation of `parts`.
+    pub fn checksum(self, parts: &[&[u8]]) -> u64 {
+        let mut digest = Digest::new(self);
+        for part in parts {
+            digest.update(part);
+        }
+        digest.finish()
+    }
+}
+
+enum Digest {
+    Crc32c(u32),
+    XxHash64(XxHash64),
+}
+
+impl Digest {
+    fn new(kind: ChecksumKind) -> Digest {
+        match kind {
+            ChecksumKind::Crc32c => Digest::Crc32c(0),
+            ChecksumKind::XxHash64 => Digest::XxHash64(XxHash64::with_seed(0)),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Digest::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+            Digest::XxHash64(hasher) => hasher.write(data),
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        match self {
+            Digest::Crc32c(crc) => *crc as u64,
+            Digest::XxHash64(hasher) => hasher.finish(),
+        }
+    }
+}
+
+/// Verifies a stored checksum over data fed in chunks, so a large value
+/// never has to be held in memory at once.
+pub struct ChecksumVerifier {
+    expected: u64,
+    digest: Digest,
+}
+
+impl ChecksumVerifier {
+    pub fn new(kind: ChecksumKind, expected: u64) -> Self {
+        ChecksumVerifier {
+            expected,
+            digest: Digest::new(kind),
+        }
+    }
+
+    /// Feeds the next chunk of the checksummed data.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.digest.update(chunk);
+    }
+
+    /// Tests the data fed so far against the expected checksum.
+    pub fn verify(&self) -> bool {
+        self.digest.finish() == self.expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn checksum_kind() {
+        for kind in [ChecksumKind::Crc32c, ChecksumKind::XxHash64] {
+            assert_eq!(ChecksumKind::from_tag(kind.tag()), Some(kind));
+            assert_eq!(
+                kind.checksum(&[b"12345", b"6789"]),
+                kind.checksum(&[b"123456789"])
+            );
+        }
+        assert_eq!(ChecksumKind::from_tag(0), None);
+
+        assert_eq!(ChecksumKind::Crc32c.checksum(&[b"123456789"]), 0xE306_9283);
+        assert!(ChecksumKind::Crc32c.checksum(&[b"123456789"]) <= u32::MAX as u64);
+    }
+
+    #[test]
+    fn checksum_verifier() {
+        let value: Vec<u8> = (0..1 << 20).map(|i| (i * 7 + i / 251) as u8).collect();
+        for kind in [ChecksumKind::Crc32c, ChecksumKind::XxHash64] {
+            let expected = kind.checksum(&[&value]);
+
+            for chunk_size in [1 << 12, 4093] {
+                let mut verifier = ChecksumVerifier::new(kind, expected);
+                for chunk in value.chunks(chunk_size) {
+                    verifier.update(chunk);
+                }
+                assert!(verifier.verify());
+
+                let mut corrupted = ChecksumVerifier::new(kind, expected ^ 1);
+                for chunk in value.chunks(chunk_size) {
+                    corrupted.update(chunk);
+                }
+                assert!(!corrupted.verify());
+            }
+
+            // Verifying before the last chunk fails.
+            let mut verifier = ChecksumVerifier::new(kind, expected);
+            verifier.update(&value[..value.len() - 1]);
+            assert!(!verifier.verify());
+            verifier.update(&value[value.len() - 1..]);
+            assert!(verifier.verify());
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{ApiV1, KvFormat, RawValue, StatusCode, ValueType};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+const DELTA_USER_VALUE: u8 = 0b001;
+const DELTA_TS: u8 = 0b010;
+const DELTA_STATUS: u8 = 0b100;
+const DELTA_VALUE_TYPE: u8 = 0b1000;
+const DELTA_REFCOUNT: u8 = 0b1_0000;
+const DELTA_DELETED_SIZE: u8 = 0b10_0000;
+
+impl ApiV1 {
+    /// Encodes only the fields of `new` that differ from the encoded `base`,
+    /// returns `None` if `base` fails to decode.
+    ///
+    /// The delta is a byte of changed-field flags followed by the changed
+    /// fields: the user value with a varint length, the ts, the status, the
+    /// value type byte, and the refcount and deleted size, each omitted if it
+    /// was removed.
+    pub fn encode_delta(base: &[u8], new: RawValue<&[u8]>) -> Option<Vec<u8>> {
+        let base = Self::decode_raw_value(base)?;
+        let ts = new.ts.unwrap_or_default();
+        let status = new.encoded_status();
+
+        let mut flags = 0;
+        let mut buf = vec![0];
+        if new.user_value != base.user_value {
+            flags |= DELTA_USER_VALUE;
+            buf.encode_var_u64(new.user_value.len() as u64).unwrap();
+            buf.extend_from_slice(new.user_value);
+        }
+        if Some(ts) != base.ts {
+            flags |= DELTA_TS;
+            buf.encode_u64(ts).unwrap();
+        }
+        if status != base.status {
+            flags |= DELTA_STATUS;
+            buf.encode_u64(status.bits()).unwrap();
+        }
+        if new.value_type != base.value_type {
+            flags |= DELTA_VALUE_TYPE;
+            buf.push(new.value_type.to_byte());
+        }
+        if new.refcount != base.refcount {
+            flags |= DELTA_REFCOUNT;
+            if let Some(refcount) = new.refcount {
+                buf.encode_u32(refcount).unwrap();
+            }
+        }
+        if new.deleted_size != base.deleted_size {
+            flags |= DELTA_DELETED_SIZE;
+            if let Some(deleted_size) = new.deleted_size {
+                buf.encode_u64(deleted_size).unwrap();
+            }
+        }
+        buf[0] = flags;
+        Some(buf)
+    }
+
+    /// Rebuilds the encoded value from `base` and a delta written by
+    /// `encode_delta`, returns `None` if either is malformed.
+    pub fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+        let mut value = Self::decode_raw_value(base)?;
+        let (&flags, mut data) = delta.split_first()?;
+        let known = DELTA_USER_VALUE
+            | DELTA_TS
+            | DELTA_STATUS
+            | DELTA_VALUE_TYPE
+            | DELTA_REFCOUNT
+            | DELTA_DELETED_SIZE;
+        if flags & !known != 0 {
+            return None;
+        }
+
+        if flags & DELTA_USER_VALUE != 0 {
+            let len = number::decode_var_u64(&mut data).ok()?;
+            if (data.len() as u64) < len {
+                return None;
+            }
+            let (user_value, rest) = data.split_at(len as usize);
+            value.user_value = user_value;
+            data = rest;
+        }
+        if flags & DELTA_TS != 0 {
+            value.ts = Some(number::decode_u64(&mut data).ok()?);
+        }
+        if flags & DELTA_STATUS != 0 {
+            value.status = StatusCode::from(number::decode_u64(&mut data).ok()?);
+            value.tombstone = value.status.is_tombstone();
+        }
+        if flags & DELTA_VALUE_TYPE != 0 {
+            let (&ty, rest) = data.split_first()?;
+            value.value_type = ValueType::from_byte(ty);
+            data = rest;
+        }
+        if flags & DELTA_REFCOUNT != 0 {
+            value.refcount = match value.status.contains(StatusCode::HAS_REFCOUNT) {
+                true => Some(number::decode_u32(&mut data).ok()?),
+                false => None,
+            };
+        }
+        if flags & DELTA_DELETED_SIZE != 0 {
+            value.deleted_size = match value.status.contains(StatusCode::HAS_DELETED_SIZE) {
+                true => Some(number::decode_u64(&mut data).ok()?),
+                false => None,
+            };
+        }
+        if !data.is_empty() {
+            return None;
+        }
+        Some(Self::encode_raw_value(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn base() -> RawValue<&'static [u8]> {
+        RawValue::new(
+            &b"some fairly long payload"[..],
+            Some(100),
+            StatusCode::from_user_status(3),
+        )
+    }
+
+    fn roundtrip(new: RawValue<&[u8]>) -> Vec<u8> {
+        let base = ApiV1::encode_raw_value(base());
+        let delta = ApiV1::encode_delta(&base, new).unwrap();
+        assert_eq!(
+            ApiV1::apply_delta(&base, &delta).unwrap(),
+            ApiV1::encode_raw_value(new)
+        );
+        delta
+    }
+
+    #[test]
+    fn delta_ts_only() {
+        let delta = roundtrip(RawValue {
+            ts: Some(200),
+            ..base()
+        });
+        assert_eq!(delta.len(), 1 + number::U64_SIZE);
+        assert_eq!(roundtrip(base()), [0]);
+    }
+
+    #[test]
+    fn delta_payload_only() {
+        let delta = roundtrip(RawValue {
+            user_value: &b"new"[..],
+            ..base()
+        });
+        assert_eq!(delta, b"\x01\x03new");
+
+        // A tombstone only changes the status.
+        let delta = roundtrip(RawValue {
+            tombstone: true,
+            ..base()
+        });
+        assert_eq!(delta[0], DELTA_STATUS);
+
+        let delta = roundtrip(RawValue {
+            value_type: ValueType::Json,
+            ..base()
+        });
+        assert_eq!(delta[0], DELTA_STATUS | DELTA_VALUE_TYPE);
+
+        let shared = RawValue {
+            refcount: Some(3),
+            ..base()
+        };
+        let delta = roundtrip(shared);
+        assert_eq!(delta[0], DELTA_STATUS | DELTA_REFCOUNT);
+        assert_eq!(delta.len(), 1 + number::U64_SIZE + number::U32_SIZE);
+
+        // Removing the refcount needs no payload.
+        let shared = ApiV1::encode_raw_value(shared);
+        let delta = ApiV1::encode_delta(&shared, base()).unwrap();
+        assert_eq!(delta.len(), 1 + number::U64_SIZE);
+        assert_eq!(
+            ApiV1::apply_delta(&shared, &delta).unwrap(),
+            ApiV1::encode_raw_value(base())
+        );
+    }
+
+    #[test]
+    fn delta_deleted_size() {
+        let tombstone = base().make_tombstone_from(Some(200));
+        let delta = roundtrip(tombstone);
+        assert_eq!(
+            delta[0],
+            DELTA_USER_VALUE | DELTA_TS | DELTA_STATUS | DELTA_DELETED_SIZE
+        );
+        assert_eq!(
+            delta[delta.len() - number::U64_SIZE..],
+            (base().encoded_len() as u64).to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn delta_payload_and_ts() {
+        let delta = roundtrip(RawValue {
+            user_value: &b""[..],
+            ts: Some(200),
+            ..base()
+        });
+        assert_eq!(delta[0], DELTA_USER_VALUE | DELTA_TS);
+
+        let base = ApiV1::encode_raw_value(base());
+        assert_eq!(ApiV1::apply_delta(&base, &delta[..delta.len() - 1]), None);
+        assert_eq!(ApiV1::apply_delta(&base, &[0x40]), None);
+        assert_eq!(ApiV1::apply_delta(&base[..8], &delta), None);
+    }
+}
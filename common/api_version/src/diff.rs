@@ -0,0 +1,197 @@
+use crate::{ApiV1, KvFormat, StatusCode, ValueType};
+
+/// How two payloads differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadDiff {
+    pub a_len: usize,
+    pub b_len: usize,
+    /// Offset of the first differing byte, or the shorter length if one
+    /// payload is a prefix of the other.
+    pub first_mismatch: usize,
+}
+
+/// The fields that differ between two values, `None` where they agree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ValueDiff {
+    pub user_value: Option<PayloadDiff>,
+    /// `b.ts - a.ts`.
+    pub ts: Option<i128>,
+    /// Status bits set in only one of the values, excluding the flags of the
+    /// other fields.
+    pub status: Option<StatusCode>,
+    /// The tombstone flags of `a` and `b`.
+    pub tombstone: Option<(bool, bool)>,
+    /// The value types of `a` and `b`.
+    pub value_type: Option<(ValueType, ValueType)>,
+    /// The refcounts of `a` and `b`.
+    pub refcount: Option<(Option<u32>, Option<u32>)>,
+    /// The deleted sizes of `a` and `b`.
+    pub deleted_size: Option<(Option<u64>, Option<u64>)>,
+}
+
+impl ValueDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == ValueDiff::default()
+    }
+}
+
+impl ApiV1 {
+    /// Compares two encoded values field by field, returns `None` if either
+    /// fails to decode.
+    pub fn diff(a: &[u8], b: &[u8]) -> Option<ValueDiff> {
+        let a = Self::decode_raw_value(a)?;
+        let b = Self::decode_raw_value(b)?;
+
+        let user_value = (a.user_value != b.user_value).then(|| PayloadDiff {
+            a_len: a.user_value.len(),
+            b_len: b.user_value.len(),
+            first_mismatch: a
+                .user_value
+                .iter()
+                .zip(b.user_value)
+                .take_while(|(x, y)| x == y)
+                .count(),
+        });
+
+        let a_ts = a.ts.unwrap_or_default() as i128;
+        let b_ts = b.ts.unwrap_or_default() as i128;
+        let ts = (a_ts != b_ts).then(|| b_ts - a_ts);
+
+        let status = (a.status ^ b.status)
+            - StatusCode::IS_TOMBSTONE
+            - StatusCode::HAS_TYPE
+            - StatusCode::HAS_REFCOUNT
+            - StatusCode::HAS_DELETED_SIZE;
+        let status = (!status.is_empty()).then(|| status);
+
+        let tombstone = (a.tombstone != b.tombstone).then(|| (a.tombstone, b.tombstone));
+        let value_type = (a.value_type != b.value_type).then(|| (a.value_type, b.value_type));
+        let refcount = (a.refcount != b.refcount).then(|| (a.refcount, b.refcount));
+        let deleted_size =
+            (a.deleted_size != b.deleted_size).then(|| (a.deleted_size, b.deleted_size));
+
+        Some(ValueDiff {
+            user_value,
+            ts,
+            status,
+            tombstone,
+            value_type,
+            refcount,
+            deleted_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::RawValue;
+
+    fn encode(user_value: &[u8], ts: u64, user_status: u64, tombstone: bool) -> Vec<u8> {
+        ApiV1::encode_raw_value(RawValue {
+            tombstone,
+            ..RawValue::new(
+                user_value,
+                Some(ts),
+                StatusCode::from_user_status(user_status),
+            )
+        })
+    }
+
+    #[test]
+    fn diff_identical() {
+        let a = encode(b"abc", 10, 1, false);
+        let diff = ApiV1::diff(&a, &a).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(ApiV1::diff(&a, &a[..4]), None);
+    }
+
+    #[test]
+    fn diff_each_field() {
+        let a = encode(b"abc", 10, 1, false);
+
+        let diff = ApiV1::diff(&a, &encode(b"abxd", 10, 1, false)).unwrap();
+        assert_eq!(
+            diff.user_value,
+            Some(PayloadDiff {
+                a_len: 3,
+                b_len: 4,
+                first_mismatch: 2
+            })
+        );
+        assert_eq!(
+            ValueDiff {
+                user_value: None,
+                ..diff
+            },
+            ValueDiff::default()
+        );
+
+        let diff = ApiV1::diff(&a, &encode(b"abc", 4, 1, false)).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                ts: Some(-6),
+                ..Default::default()
+            }
+        );
+
+        let diff = ApiV1::diff(&a, &encode(b"abc", 10, 3, false)).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                status: Some(StatusCode::from_user_status(2)),
+                ..Default::default()
+            }
+        );
+
+        let diff = ApiV1::diff(&a, &encode(b"abc", 10, 1, true)).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                tombstone: Some((false, true)),
+                ..Default::default()
+            }
+        );
+
+        let json = ApiV1::encode_raw_value(RawValue {
+            value_type: ValueType::Json,
+            ..RawValue::new(&b"abc"[..], Some(10), StatusCode::from_user_status(1))
+        });
+        let diff = ApiV1::diff(&a, &json).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                value_type: Some((ValueType::Blob, ValueType::Json)),
+                ..Default::default()
+            }
+        );
+
+        let shared = ApiV1::encode_raw_value(RawValue {
+            refcount: Some(2),
+            ..ApiV1::decode_raw_value(&a).unwrap()
+        });
+        let diff = ApiV1::diff(&a, &shared).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                refcount: Some((None, Some(2))),
+                ..Default::default()
+            }
+        );
+
+        let tombstone = ApiV1::encode_raw_value(RawValue {
+            deleted_size: Some(30),
+            ..ApiV1::decode_raw_value(&a).unwrap()
+        });
+        let diff = ApiV1::diff(&a, &tombstone).unwrap();
+        assert_eq!(
+            diff,
+            ValueDiff {
+                deleted_size: Some((None, Some(30))),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::ops::Range;
+
+use crate::ApiV1;
+
+/// Byte order a fixed-width field was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+impl ApiV1 {
+    /// Guesses the byte order of the ts in an encoded value by checking
+    /// which reading falls in `plausible_ts_range`, returns `None` if both
+    /// or neither do.
+    pub fn detect_ts_endianness(bytes: &[u8], plausible_ts_range: Range<u64>) -> Option<Endian> {
+        let raw = Self::raw_ts_bytes(bytes)?;
+        let be = plausible_ts_range.contains(&u64::from_be_bytes(raw));
+        let le = plausible_ts_range.contains(&u64::from_le_bytes(raw));
+        match (be, le) {
+            (true, false) => Some(Endian::Big),
+            (false, true) => Some(Endian::Little),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{KvFormat, RawValue, StatusCode};
+
+    // Milliseconds around late 2023 to early 2027.
+    const RECENT: Range<u64> = 1_700_000_000_000..1_800_000_000_000;
+
+    #[test]
+    fn detect_ts_endianness() {
+        let ts = 1_760_000_000_000;
+        let mut b = ApiV1::encode_raw_value(RawValue::new(&b"abc"[..], Some(ts), StatusCode::GOOD));
+        assert_eq!(ApiV1::detect_ts_endianness(&b, RECENT), Some(Endian::Big));
+
+        let start = b.len() - 16;
+        b[start..start + 8].copy_from_slice(&ts.to_le_bytes());
+        assert_eq!(
+            ApiV1::detect_ts_endianness(&b, RECENT),
+            Some(Endian::Little)
+        );
+
+        // Neither reading is plausible.
+        b[start..start + 8].copy_from_slice(&1u64.to_be_bytes());
+        assert_eq!(ApiV1::detect_ts_endianness(&b, RECENT), None);
+
+        // A palindromic ts reads the same either way.
+        b[start..start + 8].copy_from_slice(&[1; 8]);
+        assert_eq!(ApiV1::detect_ts_endianness(&b, 0..u64::MAX), None);
+
+        assert_eq!(ApiV1::detect_ts_endianness(&b[..15], 0..u64::MAX), None);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{ChecksumKind, DecodeError, KvFormat, RawValue};
+use cells_types::{Key, KvPair};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+/// Encodes `value` with `F` and appends a CRC-32C and the checksum kind tag,
+/// see `encode_entry_checked_with`.
+pub fn encode_entry_checked<F: KvFormat>(key: &Key, value: RawValue<&[u8]>) -> Vec<u8> {
+    encode_entry_checked_with::<F>(ChecksumKind::Crc32c, key, value)
+}
+
+/// Like `encode_entry_checked` with the given checksum algorithm.
+///
+/// The entry is laid out as `encoded_value || checksum || kind tag`, and
+/// the checksum covers `key || encoded_value || kind tag`, so a value stored
+/// under the wrong key or a relabelled tag fails verification too.
+pub fn encode_entry_checked_with<F: KvFormat>(
+    kind: ChecksumKind,
+    key: &Key,
+    value: RawValue<&[u8]>,
+) -> Vec<u8> {
+    let mut buf = F::encode_raw_value(value);
+    let checksum = kind.checksum(&[key.as_raw(), &buf, &[kind.tag()]]);
+    match kind {
+        ChecksumKind::Crc32c => buf.encode_u32(checksum as u32).unwrap(),
+        ChecksumKind::XxHash64 => buf.encode_u64(checksum).unwrap(),
+    }
+    buf.push(kind.tag());
+    buf
+}
+
+/// Verifies the checksum written by `encode_entry_checked*`, with the
+/// algorithm recorded in the entry, and decodes the value.
+pub fn decode_entry_checked<'a, F: KvFormat>(
+    key: &Key,
+    bytes: &'a [u8],
+) -> Result<RawValue<&'a [u8]>, DecodeError> {
+    let (kind, encoded_value, expected) = split_checked(bytes)?;
+    let actual = kind.checksum(&[key.as_raw(), encoded_value, &[kind.tag()]]);
+    if expected != actual {
+        return Err(DecodeError::ChecksumMismatch {
+            expected,
+            actual,
+            offset: encoded_value.len(),
+        });
+    }
+
+    F::try_decode_raw_value(encoded_value)
+}
+
+/// Like `decode_entry_checked`, but also requires the entry to be protected
+/// with `kind`.
+pub fn decode_entry_checked_with<'a, F: KvFormat>(
+    kind: ChecksumKind,
+    key: &Key,
+    bytes: &'a [u8],
+) -> Result<RawValue<&'a [u8]>, DecodeError> {
+    let (actual, ..) = split_checked(bytes)?;
+    if actual != kind {
+        return Err(DecodeError::ChecksumKindMismatch {
+            expected: kind,
+            actual,
+            offset: bytes.len() - 1,
+        });
+    }
+    decode_entry_checked::<F>(key, bytes)
+}
+
+/// Splits an entry into its checksum kind, encoded value and stored checksum.
+fn split_checked(bytes: &[u8]) -> Result<(ChecksumKind, &[u8], u64), DecodeError> {
+    let (&tag, rest) = bytes.split_last().ok_or_else(|| DecodeError::eof(bytes))?;
+    let kind = ChecksumKind::from_tag(tag).ok_or(DecodeError::UnknownChecksumKind {
+        tag,
+        offset: rest.len(),
+    })?;
+    let value_len = rest
+        .len()
+        .checked_sub(kind.size())
+        .ok_or_else(|| DecodeError::eof(rest))?;
+    let (encoded_value, mut checksum) = rest.split_at(value_len);
+
+    let checksum = match kind {
+        ChecksumKind::Crc32c => number::decode_u32(&mut checksum).map(u64::from),
+        ChecksumKind::XxHash64 => number::decode_u64(&mut checksum),
+    };
+    let checksum = checksum.map_err(|_| DecodeError::eof(rest))?;
+    Ok((kind, encoded_value, checksum))
+}
+
+/// A decoded key together with its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvEntry {
+    pub key: Key,
+    pub value: RawValue<Vec<u8>>,
+}
+
+impl KvEntry {
+    pub fn new(key: Key, value: RawValue<Vec<u8>>) -> KvEntry {
+        KvEntry { key, value }
+    }
+
+    /// Encodes the key and the value with `F`, returning `(raw_key, raw_value)`.
+    pub fn encode<F: KvFormat>(&self) -> (Vec<u8>, Vec<u8>) {
+        (
+            F::encode_raw_key(self.key.as_raw()).into_raw(),
+            F::encode_raw_value(self.value.as_borrowed()),
+        )
+    }
+
+    /// Decodes a pair written by `encode`. Returns `None` if the value is
+    /// malformed.
+    pub fn decode<F: KvFormat>(raw_key: &[u8], raw_value: &[u8]) -> Option<KvEntry> {
+        let key = F::decode_raw_key(&Key::from_raw(raw_key));
+        let value = F::decode_raw_value(raw_value)?;
+        Some(KvEntry {
+            key: Key::from(key),
+            value: value.to_owned_value(),
+        })
+    }
+}
+
+/// Encodes `value` with the given format and pairs it with the key bytes for
+/// a storage write.
+pub fn into_kv_pair<F: KvFormat>(key: Key, value: RawValue<&[u8]>, _format: F) -> KvPair {
+    (key.into_raw(), F::encode_raw_value(value))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{ApiV1, StatusCode};
+
+    #[test]
+    fn entry_checked_works() {
+        let key = Key::from_raw(b"key");
+        let v = RawValue::new(
+            &b"value"[..],
+            Some(1654045749000),
+            StatusCode::from_user_status(3),
+        );
+
+        let b = encode_entry_checked::<ApiV1>(&key, v);
+        assert_eq!(b.len(), ApiV1::encode_raw_value(v).len() + 4 + 1);
+        assert_eq!(decode_entry_checked::<ApiV1>(&key, &b), Ok(v));
+        assert_eq!(
+            decode_entry_checked::<ApiV1>(&key, &[]),
+            Err(DecodeError::UnexpectedEOF { offset: 0 })
+        );
+        assert_eq!(
+            decode_entry_checked::<ApiV1>(&key, &[0, 0, ChecksumKind::Crc32c.tag()]),
+            Err(DecodeError::UnexpectedEOF { offset: 2 })
+        );
+    }
+
+    #[test]
+    fn entry_checked_detects_corruption() {
+        let key = Key::from_raw(b"key");
+        let v = RawValue::new(&b"value"[..], None, StatusCode::GOOD);
+        let b = encode_entry_checked::<ApiV1>(&key, v);
+
+        // A flipped byte in the key region.
+        for i in 0..key.as_raw().len() {
+            let mut raw = key.as_raw().to_vec();
+            raw[i] ^= 0x01;
+            let res = decode_entry_checked::<ApiV1>(&Key::from_raw(&raw), &b);
+            assert!(matches!(res, Err(DecodeError::ChecksumMismatch { .. })));
+        }
+
+        // A flipped byte in the value region.
+        let mut corrupted = b;
+        corrupted[0] ^= 0x80;
+        let res = decode_entry_checked::<ApiV1>(&key, &corrupted);
+        assert!(matches!(res, Err(DecodeError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn entry_checked_kinds() {
+        let key = Key::from_raw(b"key");
+        let v = RawValue::new(&b"value"[..], Some(7), StatusCode::GOOD);
+
+        for kind in [ChecksumKind::Crc32c, ChecksumKind::XxHash64] {
+            let b = encode_entry_checked_with::<ApiV1>(kind, &key, v);
+            assert_eq!(b.len(), ApiV1::encode_raw_value(v).len() + kind.size() + 1);
+            assert_eq!(decode_entry_checked::<ApiV1>(&key, &b), Ok(v));
+            assert_eq!(decode_entry_checked_with::<ApiV1>(kind, &key, &b), Ok(v));
+
+            let res = decode_entry_checked::<ApiV1>(&Key::from_raw(b"kez"), &b);
+            assert!(matches!(res, Err(DecodeError::ChecksumMismatch { .. })));
+        }
+
+        let b = encode_entry_checked_with::<ApiV1>(ChecksumKind::XxHash64, &key, v);
+        assert_eq!(
+            decode_entry_checked_with::<ApiV1>(ChecksumKind::Crc32c, &key, &b),
+            Err(DecodeError::ChecksumKindMismatch {
+                expected: ChecksumKind::Crc32c,
+                actual: ChecksumKind::XxHash64,
+                offset: b.len() - 1,
+            })
+        );
+
+        // The tag is covered by the checksum.
+        let value_len = b.len() - 9;
+        let stored = number::decode_u64(&mut &b[value_len..b.len() - 1]).unwrap();
+        assert_eq!(
+            stored,
+            ChecksumKind::XxHash64.checksum(&[key.as_raw(), &b[..value_len], &[2]])
+        );
+
+        // Relabelling the kind makes the checksum fail to verify.
+        let mut relabelled = b;
+        *relabelled.last_mut().unwrap() = ChecksumKind::Crc32c.tag();
+        let res = decode_entry_checked::<ApiV1>(&key, &relabelled);
+        assert!(matches!(res, Err(DecodeError::ChecksumMismatch { .. })));
+
+        *relabelled.last_mut().unwrap() = 0xff;
+        assert_eq!(
+            decode_entry_checked::<ApiV1>(&key, &relabelled),
+            Err(DecodeError::UnknownChecksumKind {
+                tag: 0xff,
+                offset: relabelled.len() - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn into_kv_pair_works() {
+        let v = RawValue::new(&b"value"[..], Some(1654045749000), StatusCode::IS_TOMBSTONE);
+
+        let (key, value) = into_kv_pair(Key::from_raw(b"key"), v, ApiV1);
+        assert_eq!(key, b"key");
+        assert!(ApiV1::encode_raw_key(&key) == Key::from_raw(b"key"));
+        assert_eq!(ApiV1::decode_raw_value(&value), Some(v));
+    }
+
+    #[test]
+    fn kv_entry_roundtrip() {
+        let valid = KvEntry::new(
+            Key::from_raw(b"key"),
+            RawValue::new(b"value".to_vec(), Some(1654045749000), StatusCode::GOOD),
+        );
+        let tombstone = KvEntry::new(
+            Key::from_raw(b"gone"),
+            RawValue::new(vec![], Some(1654045750000), StatusCode::IS_TOMBSTONE),
+        );
+
+        for entry in [valid, tombstone] {
+            let (raw_key, raw_value) = entry.encode::<ApiV1>();
+            assert_eq!(raw_key, entry.key.as_raw());
+            assert_eq!(KvEntry::decode::<ApiV1>(&raw_key, &raw_value), Some(entry));
+        }
+        assert_eq!(KvEntry::decode::<ApiV1>(b"key", &[0; 3]), None);
+    }
+}
@@ -0,0 +1,132 @@
+use snafu::Snafu;
+
+use crate::ChecksumKind;
+
+/// Every variant records a byte `offset` into the decoded buffer.
+///
+/// Header, checksum and tag errors point at the offending field, and value
+/// checks such as `ValueTooLarge` at the start of the user value. An
+/// `UnexpectedEOF` points at where the decoder started reading the field it
+/// ran out of bytes for: its start for fields read front to back, and its
+/// end for the trailer fields, such as the ts and status, that are read
+/// back to front from the end of the buffer.
+#[derive(Debug, Snafu, PartialEq, Eq)]
+pub enum DecodeError {
+    #[snafu(display("Decode Error: Bad magic {:02X?} at offset {}", magic, offset))]
+    BadMagic { magic: [u8; 2], offset: usize },
+
+    #[snafu(display("Decode Error: Unsupported version {} at offset {}", version, offset))]
+    UnsupportedVersion { version: u8, offset: usize },
+
+    #[snafu(display(
+        "Decode Error: Checksum mismatch, expected 0x{:X} got 0x{:X} at offset {}",
+        expected,
+        actual,
+        offset
+    ))]
+    ChecksumMismatch {
+        expected: u64,
+        actual: u64,
+        offset: usize,
+    },
+
+    #[snafu(display(
+        "Decode Error: Checksum kind mismatch, expected {:?} got {:?} at offset {}",
+        expected,
+        actual,
+        offset
+    ))]
+    ChecksumKindMismatch {
+        expected: ChecksumKind,
+        actual: ChecksumKind,
+        offset: usize,
+    },
+
+    #[snafu(display("Decode Error: Unknown checksum kind {} at offset {}", tag, offset))]
+    UnknownChecksumKind { tag: u8, offset: usize },
+
+    #[snafu(display(
+        "Decode Error: Value of {} bytes exceeds the limit {} at offset {}",
+        len,
+        max,
+        offset
+    ))]
+    ValueTooLarge {
+        len: usize,
+        max: usize,
+        offset: usize,
+    },
+
+    #[snafu(display(
+        "Decode Error: Tombstone with a {} byte payload at offset {}",
+        len,
+        offset
+    ))]
+    TombstoneWithPayload { len: usize, offset: usize },
+
+    #[snafu(display("Decode Error: Unexpected eof at offset {}", offset))]
+    UnexpectedEOF { offset: usize },
+}
+
+impl DecodeError {
+    /// The byte offset at which decoding failed.
+    pub fn offset(&self) -> usize {
+        match *self {
+            DecodeError::BadMagic { offset, .. }
+            | DecodeError::UnsupportedVersion { offset, .. }
+            | DecodeError::ChecksumMismatch { offset, .. }
+            | DecodeError::ChecksumKindMismatch { offset, .. }
+            | DecodeError::UnknownChecksumKind { offset, .. }
+            | DecodeError::ValueTooLarge { offset, .. }
+            | DecodeError::TombstoneWithPayload { offset, .. }
+            | DecodeError::UnexpectedEOF { offset } => offset,
+        }
+    }
+
+    /// An `UnexpectedEOF` at the end of `bytes`, for decoders that can't
+    /// tell where in `bytes` they failed.
+    pub(crate) fn eof(bytes: &[u8]) -> DecodeError {
+        DecodeError::UnexpectedEOF {
+            offset: bytes.len(),
+        }
+    }
+
+    /// Moves the offset of an error in a part of a buffer that starts at
+    /// `base` to the whole buffer.
+    pub(crate) fn offset_by(mut self, base: usize) -> DecodeError {
+        match &mut self {
+            DecodeError::BadMagic { offset, .. }
+            | DecodeError::UnsupportedVersion { offset, .. }
+            | DecodeError::ChecksumMismatch { offset, .. }
+            | DecodeError::ChecksumKindMismatch { offset, .. }
+            | DecodeError::UnknownChecksumKind { offset, .. }
+            | DecodeError::ValueTooLarge { offset, .. }
+            | DecodeError::TombstoneWithPayload { offset, .. }
+            | DecodeError::UnexpectedEOF { offset } => *offset += base,
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn display_includes_offset() {
+        let e = DecodeError::UnexpectedEOF { offset: 13 };
+        assert_eq!(e.to_string(), "Decode Error: Unexpected eof at offset 13");
+        assert_eq!(e.offset(), 13);
+
+        let e = DecodeError::BadMagic {
+            magic: [0xAB, 0x11],
+            offset: 0,
+        };
+        assert_eq!(
+            e.to_string(),
+            "Decode Error: Bad magic [AB, 11] at offset 0"
+        );
+        assert_eq!(e.offset(), 0);
+    }
+}
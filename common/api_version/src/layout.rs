@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use crate::{ApiV1, KvFormat};
+use cells_utils::codec::number;
+
+/// Byte ranges of each field within an encoded `ApiV1` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldOffsets {
+    pub user_value: Range<usize>,
+    /// The fields flagged in the status, empty if there are none.
+    pub optional_fields: Range<usize>,
+    pub ts: Range<usize>,
+    pub status: Range<usize>,
+}
+
+impl ApiV1 {
+    /// Locates the fields of an encoded value, e.g. to annotate a hex dump.
+    pub fn field_offsets(bytes: &[u8]) -> Option<FieldOffsets> {
+        let status_start = bytes.len().checked_sub(number::U64_SIZE)?;
+        let ts_start = status_start.checked_sub(number::U64_SIZE)?;
+        let user_value_end = Self::decode_raw_value(bytes)?.user_value.len();
+
+        Some(FieldOffsets {
+            user_value: 0..user_value_end,
+            optional_fields: user_value_end..ts_start,
+            ts: ts_start..status_start,
+            status: status_start..bytes.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{RawValue, StatusCode, ValueType};
+
+    #[test]
+    fn field_offsets() {
+        let v = RawValue::new(
+            &b"hello"[..],
+            Some(1654045749000),
+            StatusCode::from_user_status(3),
+        );
+        let b = ApiV1::encode_raw_value(v);
+        assert_eq!(
+            ApiV1::field_offsets(&b),
+            Some(FieldOffsets {
+                user_value: 0..5,
+                optional_fields: 5..5,
+                ts: 5..13,
+                status: 13..21,
+            })
+        );
+
+        let offsets = ApiV1::field_offsets(&b).unwrap();
+        assert_eq!(&b[offsets.user_value], v.user_value);
+        assert_eq!(b[offsets.ts], ApiV1::raw_ts_bytes(&b).unwrap());
+
+        let tombstone = ApiV1::encode_raw_value(RawValue {
+            user_value: &b""[..],
+            tombstone: true,
+            ..v
+        });
+        assert_eq!(
+            ApiV1::field_offsets(&tombstone),
+            Some(FieldOffsets {
+                user_value: 0..0,
+                optional_fields: 0..0,
+                ts: 0..8,
+                status: 8..16,
+            })
+        );
+
+        let typed = ApiV1::encode_raw_value(RawValue {
+            value_type: ValueType::Counter,
+            ..v
+        });
+        let offsets = ApiV1::field_offsets(&typed).unwrap();
+        assert_eq!(offsets.user_value, 0..5);
+        assert_eq!(
+            &typed[offsets.optional_fields],
+            &[ValueType::Counter.to_byte()]
+        );
+        assert_eq!(offsets.ts, 6..14);
+        assert_eq!(ApiV1::field_offsets(&tombstone[..15]), None);
+    }
+}
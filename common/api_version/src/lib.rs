@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cells_types::Key;
+use cells_types::{Key, KeyRef};
 use cells_utils::codec::number::{self, NumberEncoder};
 
 #[derive(Default, Clone, Copy)]
 pub struct ApiV1;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RawValue<T: AsRef<[u8]>> {
     /// The user value.
     pub user_value: T,
@@ -28,13 +29,133 @@ pub struct RawValue<T: AsRef<[u8]>> {
     pub status: StatusCode,
     /// The tombstone status
     pub tombstone: bool,
+    /// The logical type of the user value
+    pub value_type: ValueType,
+    /// The number of references to a deduplicated value
+    pub refcount: Option<u32>,
+    /// The encoded length of the value a tombstone deleted
+    pub deleted_size: Option<u64>,
 }
 
 impl<T: AsRef<[u8]>> RawValue<T> {
+    /// Builds an untyped value, a tombstone if `status` has `IS_TOMBSTONE`.
+    pub fn new(user_value: T, ts: Option<u64>, status: StatusCode) -> Self {
+        RawValue {
+            user_value,
+            ts,
+            status,
+            tombstone: status.is_tombstone(),
+            value_type: ValueType::Blob,
+            refcount: None,
+            deleted_size: None,
+        }
+    }
+
     #[inline]
     pub fn is_valid(&self) -> bool {
         !self.tombstone
     }
+
+    /// The ts viewed as a signed timestamp, as written by `ApiV1SignedTs`.
+    ///
+    /// This only reinterprets the bits of `ts`: it is the signed ts for
+    /// values decoded with `ApiV1SignedTs`, and for plain `ApiV1` values
+    /// just their ts as an `i64`.
+    #[inline]
+    pub fn ts_signed(&self) -> Option<i64> {
+        self.ts.map(|ts| ts as i64)
+    }
+
+    /// The index of the first of the ascending age `thresholds` that the
+    /// value is younger than at `now`, or `thresholds.len()` if it is older
+    /// than all of them or has no ts.
+    pub fn age_bucket(&self, now: u64, thresholds: &[u64]) -> usize {
+        match self.ts {
+            Some(ts) => {
+                let age = now.saturating_sub(ts);
+                thresholds.partition_point(|&threshold| threshold <= age)
+            }
+            None => thresholds.len(),
+        }
+    }
+
+    /// The length of the value encoded with `ApiV1`.
+    pub fn encoded_len(&self) -> usize {
+        let optional_len = ApiV1::optional_fields_len(self.encoded_status());
+        self.user_value.as_ref().len() + optional_len + number::U64_SIZE + number::U64_SIZE
+    }
+
+    /// The status as encoded, with the flags implied by the other fields.
+    pub(crate) fn encoded_status(&self) -> StatusCode {
+        let mut status = self.status;
+        if self.tombstone {
+            status.insert(StatusCode::IS_TOMBSTONE);
+        }
+        status.set(StatusCode::HAS_TYPE, self.value_type != ValueType::Blob);
+        status.set(StatusCode::HAS_REFCOUNT, self.refcount.is_some());
+        status.set(StatusCode::HAS_DELETED_SIZE, self.deleted_size.is_some());
+        status
+    }
+
+    /// Builds the tombstone deleting this value, which records its encoded
+    /// length for space accounting.
+    pub fn make_tombstone_from(&self, ts: Option<u64>) -> RawValue<&'static [u8]> {
+        RawValue {
+            deleted_size: Some(self.encoded_len() as u64),
+            ..RawValue::new(&[][..], ts, StatusCode::IS_TOMBSTONE)
+        }
+    }
+
+    pub(crate) fn as_borrowed(&self) -> RawValue<&[u8]> {
+        RawValue {
+            user_value: self.user_value.as_ref(),
+            ts: self.ts,
+            status: self.status,
+            tombstone: self.tombstone,
+            value_type: self.value_type,
+            refcount: self.refcount,
+            deleted_size: self.deleted_size,
+        }
+    }
+
+    pub(crate) fn to_owned_value(&self) -> RawValue<Vec<u8>> {
+        RawValue {
+            user_value: self.user_value.as_ref().to_vec(),
+            ts: self.ts,
+            status: self.status,
+            tombstone: self.tombstone,
+            value_type: self.value_type,
+            refcount: self.refcount,
+            deleted_size: self.deleted_size,
+        }
+    }
+}
+
+/// Generates canonical values, which `ApiV1` decodes back unchanged: the ts
+/// is always set and the tombstone flag and optional fields agree with the
+/// status.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for RawValue<Vec<u8>> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let user_value = u.arbitrary()?;
+        let ts = u.arbitrary()?;
+        let mut status: StatusCode = u.arbitrary()?;
+        let value_type = ValueType::from_byte(u.arbitrary()?);
+        let refcount = u.arbitrary::<bool>()?.then(|| u.arbitrary()).transpose()?;
+        let deleted_size = u.arbitrary::<bool>()?.then(|| u.arbitrary()).transpose()?;
+        status.set(StatusCode::HAS_TYPE, value_type != ValueType::Blob);
+        status.set(StatusCode::HAS_REFCOUNT, refcount.is_some());
+        status.set(StatusCode::HAS_DELETED_SIZE, deleted_size.is_some());
+        Ok(RawValue {
+            user_value,
+            ts: Some(ts),
+            status,
+            tombstone: status.is_tombstone(),
+            value_type,
+            refcount,
+            deleted_size,
+        })
+    }
 }
 
 pub trait KvFormat: Clone + Copy + 'static + Send + Sync {
@@ -42,8 +163,28 @@ pub trait KvFormat: Clone + Copy + 'static + Send + Sync {
     fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8>;
     fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8>;
 
+    /// `decode_raw_value` with the lifetime named for generic code: the
+    /// result borrows `bytes` and nothing else, so it lives as long as the
+    /// input buffer.
+    #[allow(clippy::needless_lifetimes)]
+    fn decode_raw_value_ref<'a>(bytes: &'a [u8]) -> Option<RawValue<&'a [u8]>> {
+        Self::decode_raw_value(bytes)
+    }
+
+    /// `decode_raw_value` reporting why and where decoding failed. Formats
+    /// that don't override it report every failure as an `UnexpectedEOF` at
+    /// the end of `bytes`.
+    fn try_decode_raw_value(bytes: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        Self::decode_raw_value(bytes).ok_or_else(|| DecodeError::eof(bytes))
+    }
+
     fn decode_raw_key(key: &Key) -> Vec<u8> {
-        key.as_raw().clone()
+        key.as_raw().to_vec()
+    }
+
+    /// `decode_raw_key` without the copy, borrowing from `key`.
+    fn decode_raw_key_ref(key: &Key) -> KeyRef<'_> {
+        key.as_key_ref()
     }
 
     fn encode_raw_key(key: &[u8]) -> Key {
@@ -51,17 +192,34 @@ pub trait KvFormat: Clone + Copy + 'static + Send + Sync {
     }
 }
 
+/// Moves an encoded `key` from the `Src` key encoding to the `Dst` one,
+/// leaving the value alone.
+pub fn rekey<Src: KvFormat, Dst: KvFormat>(key: &[u8]) -> Key {
+    Dst::encode_raw_key(&Src::decode_raw_key(&Key::from_raw(key)))
+}
+
 impl KvFormat for ApiV1 {
     fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
-        let mut rest_len = bytes.len().checked_sub(number::U64_SIZE)?;
+        Self::try_decode_raw_value(bytes).ok()
+    }
+
+    fn try_decode_raw_value(bytes: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        let eof = |offset| DecodeError::UnexpectedEOF { offset };
+        let mut rest_len = bytes.len();
+        rest_len = rest_len
+            .checked_sub(number::U64_SIZE)
+            .ok_or_else(|| eof(rest_len))?;
         let mut status_slice = &bytes[rest_len..];
         let s = number::decode_u64(&mut status_slice).unwrap_or_default();
         let status = StatusCode::from(s);
-        let tombstone = if status.is_tombstone() { true } else { false };
+        let tombstone = status.is_tombstone();
 
-        rest_len = rest_len.checked_sub(number::U64_SIZE)?;
+        rest_len = rest_len
+            .checked_sub(number::U64_SIZE)
+            .ok_or_else(|| eof(rest_len))?;
         let mut ts_slice = &bytes[rest_len..rest_len + number::U64_SIZE];
         let ts = number::decode_u64(&mut ts_slice).unwrap_or_default();
+        let (user_value, optional) = Self::split_optional_fields(&bytes[..rest_len], status)?;
 
         // let status = bytes.len().checked_sub(number::U64_SIZE).and_then(|l| {
         //     rest_len = l;
@@ -70,56 +228,159 @@ impl KvFormat for ApiV1 {
         //     Some(StatusCode::from(s))
         // });
 
-        Some(RawValue {
-            user_value: &bytes[..rest_len],
+        Ok(RawValue {
+            user_value,
             ts: Some(ts),
             status,
             tombstone,
+            value_type: optional.value_type,
+            refcount: optional.refcount,
+            deleted_size: optional.deleted_size,
         })
     }
 
     fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
-        let mut buf: Vec<u8> =
-            Vec::with_capacity(value.user_value.len() + number::U64_SIZE + number::U64_SIZE);
+        let mut buf: Vec<u8> = Vec::with_capacity(value.encoded_len());
 
         buf.extend_from_slice(value.user_value);
+        Self::encode_trailer(&mut buf, &value);
 
-        let ts = value.ts.unwrap_or_default();
-        buf.encode_u64(ts).unwrap();
-        let mut status = value.status;
-        if value.tombstone {
-            status.insert(StatusCode::IS_TOMBSTONE);
+        buf
+    }
+
+    fn encode_raw_value_owned(mut value: RawValue<Vec<u8>>) -> Vec<u8> {
+        let len = value.encoded_len();
+        let mut buf = std::mem::take(&mut value.user_value);
+        buf.reserve(len - buf.len());
+        Self::encode_trailer(&mut buf, &value);
+        buf
+    }
+}
+
+impl ApiV1 {
+    /// Returns the ts field exactly as stored, without decoding it.
+    pub fn raw_ts_bytes(bytes: &[u8]) -> Option<[u8; number::U64_SIZE]> {
+        let end = bytes.len().checked_sub(number::U64_SIZE)?;
+        let start = end.checked_sub(number::U64_SIZE)?;
+        bytes[start..end].try_into().ok()
+    }
+
+    /// Decodes `bytes`, rejecting values whose user value is larger than
+    /// `max_value_len` bytes.
+    pub fn decode_raw_value_bounded(
+        bytes: &[u8],
+        max_value_len: usize,
+    ) -> Result<RawValue<&[u8]>, DecodeError> {
+        let value = Self::try_decode_raw_value(bytes)?;
+        let len = value.user_value.len();
+        if len > max_value_len {
+            return Err(DecodeError::ValueTooLarge {
+                len,
+                max: max_value_len,
+                offset: 0,
+            });
         }
+        Ok(value)
+    }
 
-        buf.encode_u64(status.bits()).unwrap();
+    /// Decodes `bytes`, rejecting tombstones that carry a user value, which
+    /// only a corrupted buffer or a buggy writer produces. The error points
+    /// at the status that marks the value as a tombstone.
+    pub fn decode_raw_value_strict(bytes: &[u8]) -> Result<RawValue<&[u8]>, DecodeError> {
+        let value = Self::try_decode_raw_value(bytes)?;
+        if value.tombstone && !value.user_value.is_empty() {
+            return Err(DecodeError::TombstoneWithPayload {
+                len: value.user_value.len(),
+                offset: bytes.len() - number::U64_SIZE,
+            });
+        }
+        Ok(value)
+    }
 
-        buf
+    /// Decodes and re-encodes `bytes`, so that byte-equality of the results
+    /// implies logical equality of the values.
+    pub fn canonical_encoding(bytes: &[u8]) -> Option<Vec<u8>> {
+        Self::decode_raw_value(bytes).map(Self::encode_raw_value)
     }
 
-    fn encode_raw_value_owned(mut value: RawValue<Vec<u8>>) -> Vec<u8> {
-        value
-            .user_value
-            .reserve(number::U64_SIZE + number::U64_SIZE);
-        value
-            .user_value
-            .encode_u64(value.ts.unwrap_or_default())
-            .unwrap();
-        if value.tombstone {
-            value.status.insert(StatusCode::IS_TOMBSTONE);
+    /// Whether compaction can drop `older` in favor of `newer`: both decode,
+    /// neither is a tombstone, `older` has the smaller ts and they only
+    /// differ in ts.
+    pub fn is_redundant(older: &[u8], newer: &[u8]) -> bool {
+        match (Self::decode_raw_value(older), Self::decode_raw_value(newer)) {
+            (Some(older), Some(newer)) => {
+                older.ts < newer.ts
+                    && !older.tombstone
+                    && !newer.tombstone
+                    && RawValue {
+                        ts: newer.ts,
+                        ..older
+                    } == newer
+            }
+            _ => false,
         }
-        value.user_value.encode_u64(value.status.bits()).unwrap();
-        value.user_value
+    }
+
+    /// Appends the optional fields, ts and status trailer of `value` to `buf`.
+    pub(crate) fn encode_trailer<T: AsRef<[u8]>>(buf: &mut Vec<u8>, value: &RawValue<T>) {
+        Self::encode_optional_fields(buf, value);
+        let ts = value.ts.unwrap_or_default();
+        buf.encode_u64(ts).unwrap();
+        buf.encode_u64(value.encoded_status().bits()).unwrap();
     }
 }
 
+mod api_v1_append;
+mod api_v1_compact;
+mod api_v1_magic;
+mod api_v1_signed_ts;
+mod api_v1_tagged;
+mod batch;
+mod checksum;
+mod delta;
+mod diff;
+mod endian;
+mod entry;
+mod error;
+mod layout;
+mod optional_fields;
+mod raw_passthrough;
+mod refcount;
+mod scan;
 mod status_code;
-use status_code::StatusCode;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod value_list;
+mod value_type;
+mod view;
+pub use api_v1_append::ApiV1Append;
+pub use api_v1_compact::ApiV1Compact;
+pub use api_v1_magic::ApiV1Magic;
+pub use api_v1_signed_ts::ApiV1SignedTs;
+pub use api_v1_tagged::ApiV1Tagged;
+pub use batch::{batch_encoded_len, decode_batch_collect};
+pub use checksum::{ChecksumKind, ChecksumVerifier};
+pub use diff::*;
+pub use endian::Endian;
+pub use entry::*;
+pub use error::DecodeError;
+pub use layout::FieldOffsets;
+pub use raw_passthrough::RawPassthrough;
+pub use scan::BoundedScan;
+pub use status_code::{StatusCode, SystemStatus, UserFlagOutOfRange, UserStatus};
+pub use value_list::*;
+pub use value_type::ValueType;
+pub use view::ValueView;
 
 #[cfg(test)]
 mod tests {
 
-    use crate::{status_code::StatusCode, ApiV1, KvFormat, RawValue};
+    use crate::{
+        status_code::StatusCode, ApiV1, ApiV1Append, ApiV1Compact, ApiV1Magic, DecodeError,
+        KvFormat, RawValue, ValueType,
+    };
     use cells_types::Key;
+    use cells_utils::codec::number::NumberEncoder;
 
     #[test]
     fn api_v1_works() {
@@ -128,10 +389,12 @@ mod tests {
         assert_eq!(a, b"123");
 
         let v = RawValue {
-            user_value: &b"123"[..],
-            ts: Some(1654045749000),
-            status: StatusCode::from_user_status(123),
             tombstone: true,
+            ..RawValue::new(
+                &b"123"[..],
+                Some(1654045749000),
+                StatusCode::from_user_status(123),
+            )
         };
 
         let b = ApiV1::encode_raw_value(v);
@@ -141,17 +404,12 @@ mod tests {
         assert_eq!(v1.unwrap().user_value, b"123".to_vec());
         assert_eq!(v1.unwrap().ts, Some(1654045749000));
         assert_eq!(v1.unwrap().status.user_status(), 123u64.into());
-        assert_eq!(v1.unwrap().tombstone, true);
+        assert!(v1.unwrap().tombstone);
         assert!(!v1.unwrap().is_valid());
 
         // Test Null Value
 
-        let v = RawValue {
-            user_value: vec![],
-            ts: None,
-            status: StatusCode::GOOD,
-            tombstone: false,
-        };
+        let v = RawValue::new(vec![], None, StatusCode::GOOD);
 
         let b = ApiV1::encode_raw_value_owned(v);
         assert_eq!(b, &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
@@ -163,4 +421,326 @@ mod tests {
         assert_eq!(v1.unwrap().status, 0u64.into());
         assert!(v1.unwrap().is_valid());
     }
+
+    #[test]
+    fn raw_value_new() {
+        let v = RawValue::new(&b"abc"[..], Some(3), StatusCode::from_user_status(7));
+        assert_eq!(v.user_value, b"abc");
+        assert_eq!(v.ts, Some(3));
+        assert!(!v.tombstone);
+        assert_eq!(v.value_type, ValueType::Blob);
+
+        let status = StatusCode::from_user_status(7) | StatusCode::IS_TOMBSTONE;
+        let v = RawValue::new(vec![], None, status);
+        assert!(v.tombstone);
+        assert_eq!(v.status, status);
+        assert_eq!(
+            ApiV1::decode_raw_value(&ApiV1::encode_raw_value_owned(v)),
+            Some(RawValue::new(&b""[..], Some(0), status))
+        );
+    }
+
+    #[test]
+    fn age_bucket() {
+        let thresholds = [10, 100, 1000];
+        let at = |ts| RawValue::new(&b""[..], ts, StatusCode::GOOD).age_bucket(5000, &thresholds);
+        assert_eq!(at(Some(5000)), 0);
+        assert_eq!(at(Some(4991)), 0);
+        assert_eq!(at(Some(4990)), 1);
+        assert_eq!(at(Some(4901)), 1);
+        assert_eq!(at(Some(4900)), 2);
+        assert_eq!(at(Some(4000)), 3);
+        assert_eq!(at(Some(0)), 3);
+        assert_eq!(at(None), 3);
+        // A ts from the future is as hot as it gets.
+        assert_eq!(at(Some(6000)), 0);
+
+        let v = RawValue::new(&b""[..], Some(1), StatusCode::GOOD);
+        assert_eq!(v.age_bucket(100, &[]), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let value = RawValue {
+            value_type: ValueType::Counter,
+            refcount: Some(2),
+            ..RawValue::new(b"abc".to_vec(), Some(7), StatusCode::from_user_status(3))
+        };
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RawValue<Vec<u8>>>(&json).unwrap(),
+            value
+        );
+        let bin = bincode::serialize(&value).unwrap();
+        assert_eq!(
+            bincode::deserialize::<RawValue<Vec<u8>>>(&bin).unwrap(),
+            value
+        );
+
+        let status = StatusCode::IS_TOMBSTONE | StatusCode::from_user_status(5);
+        assert_eq!(
+            serde_json::to_string(&status).unwrap(),
+            status.bits().to_string()
+        );
+        assert_eq!(
+            serde_json::from_str::<StatusCode>("5").unwrap(),
+            status.user_status()
+        );
+    }
+
+    /// `ApiV1` values under a one byte mode prefix.
+    #[derive(Clone, Copy)]
+    struct Prefixed;
+
+    impl KvFormat for Prefixed {
+        fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+            ApiV1::decode_raw_value(bytes)
+        }
+
+        fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+            ApiV1::encode_raw_value(value)
+        }
+
+        fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+            ApiV1::encode_raw_value_owned(value)
+        }
+
+        fn decode_raw_key(key: &Key) -> Vec<u8> {
+            key.as_raw()[1..].to_vec()
+        }
+
+        fn encode_raw_key(key: &[u8]) -> Key {
+            Key::from_raw(&[&b"r"[..], key].concat())
+        }
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    fn user_value_of<'a, F: KvFormat>(bytes: &'a [u8]) -> &'a [u8] {
+        F::decode_raw_value_ref(bytes).unwrap().user_value
+    }
+
+    #[test]
+    fn decode_raw_value_ref() {
+        let b = ApiV1Magic::encode_raw_value(RawValue::new(&b"abc"[..], Some(1), StatusCode::GOOD));
+        // The borrow returned by the helper is still usable here.
+        let user_value = user_value_of::<ApiV1Magic>(&b);
+        // And the lifetime survives coercion to a plain function pointer.
+        let decode: fn(&[u8]) -> Option<RawValue<&[u8]>> = ApiV1Magic::decode_raw_value_ref;
+        let decoded = decode(&b).unwrap();
+        assert_eq!(user_value, b"abc");
+        assert_eq!(decoded.user_value, user_value);
+    }
+
+    #[test]
+    fn decode_raw_key_ref() {
+        let key = ApiV1::encode_raw_key(b"k1");
+        let raw = ApiV1::decode_raw_key_ref(&key);
+        // Borrowed straight from the key, nothing was copied.
+        assert_eq!(raw.as_raw().as_ptr(), key.as_raw().as_ptr());
+        assert_eq!(raw.as_raw(), ApiV1::decode_raw_key(&key));
+    }
+
+    #[test]
+    fn rekey() {
+        let v2 = super::rekey::<ApiV1, Prefixed>(b"k1");
+        assert_eq!(v2.as_raw(), b"rk1");
+        assert_eq!(super::rekey::<Prefixed, ApiV1>(v2.as_raw()).as_raw(), b"k1");
+        assert_eq!(super::rekey::<ApiV1, ApiV1>(b"").as_raw(), b"");
+    }
+
+    #[test]
+    fn deleted_size() {
+        let prior = RawValue::new(&b"some payload"[..], Some(1), StatusCode::GOOD);
+        let tombstone = prior.make_tombstone_from(Some(2));
+        assert!(tombstone.tombstone);
+        assert_eq!(tombstone.deleted_size, Some(12 + 16));
+
+        let expected = RawValue {
+            status: StatusCode::IS_TOMBSTONE | StatusCode::HAS_DELETED_SIZE,
+            ..tombstone
+        };
+        let b = ApiV1::encode_raw_value(tombstone);
+        assert_eq!(b.len(), tombstone.encoded_len());
+        assert_eq!(ApiV1::decode_raw_value(&b).unwrap(), expected);
+        let b = ApiV1Compact::encode_raw_value(tombstone);
+        assert_eq!(ApiV1Compact::decode_raw_value(&b).unwrap(), expected);
+        let b = ApiV1Append::encode_raw_value(tombstone);
+        assert_eq!(ApiV1Append::decode_raw_value(&b).unwrap(), expected);
+
+        let plain = RawValue::new(&b""[..], Some(2), StatusCode::IS_TOMBSTONE);
+        let b = ApiV1::encode_raw_value(plain);
+        let decoded = ApiV1::decode_raw_value(&b).unwrap();
+        assert_eq!(decoded.deleted_size, None);
+        assert!(!decoded.status.contains(StatusCode::HAS_DELETED_SIZE));
+
+        // The refcount stays at its offset from the end.
+        let mut b = ApiV1::encode_raw_value(RawValue {
+            refcount: Some(1),
+            ..tombstone
+        });
+        assert_eq!(ApiV1::increment_refcount(&mut b), Some(2));
+        assert_eq!(ApiV1::decode_raw_value(&b).unwrap().deleted_size, Some(28));
+    }
+
+    #[test]
+    fn raw_ts_bytes() {
+        let ts = 0x0102_0304_0506_0708u64;
+        let b = ApiV1::encode_raw_value(RawValue {
+            tombstone: true,
+            ..RawValue::new(&b"abc"[..], Some(ts), StatusCode::GOOD)
+        });
+        assert_eq!(ApiV1::raw_ts_bytes(&b), Some(ts.to_be_bytes()));
+        assert_eq!(ApiV1::raw_ts_bytes(&b[..15]), None);
+    }
+
+    #[test]
+    fn decode_bounded() {
+        let b = ApiV1::encode_raw_value(RawValue::new(&[7; 32][..], Some(1), StatusCode::GOOD));
+
+        let v = ApiV1::decode_raw_value_bounded(&b, 32).unwrap();
+        assert_eq!(v.user_value, &[7; 32]);
+        assert_eq!(
+            ApiV1::decode_raw_value_bounded(&b, 31),
+            Err(DecodeError::ValueTooLarge {
+                len: 32,
+                max: 31,
+                offset: 0
+            })
+        );
+        assert_eq!(
+            ApiV1::decode_raw_value_bounded(&b[..15], 32),
+            Err(DecodeError::UnexpectedEOF { offset: 7 })
+        );
+    }
+
+    #[test]
+    fn decode_strict() {
+        let tombstone =
+            ApiV1::encode_raw_value(RawValue::new(&b""[..], Some(1), StatusCode::IS_TOMBSTONE));
+        let v = ApiV1::decode_raw_value_strict(&tombstone).unwrap();
+        assert!(v.tombstone);
+
+        let bad = ApiV1::encode_raw_value(RawValue::new(
+            &b"abc"[..],
+            Some(1),
+            StatusCode::IS_TOMBSTONE,
+        ));
+        assert_eq!(
+            ApiV1::decode_raw_value_strict(&bad),
+            Err(DecodeError::TombstoneWithPayload { len: 3, offset: 11 })
+        );
+        assert!(ApiV1::decode_raw_value(&bad).unwrap().tombstone);
+
+        let live = ApiV1::encode_raw_value(RawValue::new(&b"abc"[..], Some(1), StatusCode::GOOD));
+        assert!(ApiV1::decode_raw_value_strict(&live).is_ok());
+        assert_eq!(
+            ApiV1::decode_raw_value_strict(&live[..4]),
+            Err(DecodeError::UnexpectedEOF { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn canonical_encoding() {
+        // The tombstone may be carried by the flag or by the status.
+        let a = ApiV1::encode_raw_value(RawValue {
+            tombstone: true,
+            ..RawValue::new(&b"abc"[..], None, StatusCode::from_user_status(5))
+        });
+        let b = ApiV1::encode_raw_value(RawValue {
+            tombstone: false,
+            ..RawValue::new(
+                &b"abc"[..],
+                Some(0),
+                StatusCode::from_user_status(5) | StatusCode::IS_TOMBSTONE,
+            )
+        });
+
+        let ca = ApiV1::canonical_encoding(&a).unwrap();
+        assert_eq!(ca, ApiV1::canonical_encoding(&b).unwrap());
+        assert_eq!(ApiV1::canonical_encoding(&ca).unwrap(), ca);
+
+        let c = ApiV1::encode_raw_value(RawValue {
+            tombstone: true,
+            ..RawValue::new(&b"abd"[..], None, StatusCode::from_user_status(5))
+        });
+        assert_ne!(ca, ApiV1::canonical_encoding(&c).unwrap());
+        assert_eq!(ApiV1::canonical_encoding(&c[..8]), None);
+    }
+
+    #[test]
+    fn is_redundant() {
+        let at = |user_value: &[u8], ts, status| {
+            ApiV1::encode_raw_value(RawValue::new(user_value, Some(ts), status))
+        };
+        let status = StatusCode::from_user_status(2);
+        let older = at(b"abc", 1, status);
+
+        assert!(ApiV1::is_redundant(&older, &at(b"abc", 2, status)));
+        assert!(!ApiV1::is_redundant(&older, &at(b"abc", 1, status)));
+        assert!(!ApiV1::is_redundant(&at(b"abc", 2, status), &older));
+        assert!(!ApiV1::is_redundant(&older, &at(b"abd", 2, status)));
+        assert!(!ApiV1::is_redundant(
+            &older,
+            &at(b"abc", 2, StatusCode::GOOD)
+        ));
+
+        // A tombstone on either side is a boundary.
+        let tombstone = status | StatusCode::IS_TOMBSTONE;
+        assert!(!ApiV1::is_redundant(&older, &at(b"abc", 2, tombstone)));
+        assert!(!ApiV1::is_redundant(
+            &at(b"abc", 1, tombstone),
+            &at(b"abc", 2, tombstone)
+        ));
+
+        let typed = ApiV1::encode_raw_value(RawValue {
+            value_type: ValueType::Json,
+            ..RawValue::new(&b"abc"[..], Some(2), status)
+        });
+        assert!(!ApiV1::is_redundant(&older, &typed));
+        assert!(!ApiV1::is_redundant(&older, &older[..8]));
+    }
+
+    #[test]
+    fn value_type() {
+        let types = [
+            ValueType::Blob,
+            ValueType::Json,
+            ValueType::Counter,
+            ValueType::Unknown(3),
+        ];
+        for value_type in types {
+            let v = RawValue {
+                tombstone: true,
+                value_type,
+                ..RawValue::new(&b"{}"[..], Some(5), StatusCode::from_user_status(1))
+            };
+            let b = ApiV1::encode_raw_value(v);
+            let typed = value_type != ValueType::Blob;
+            assert_eq!(b.len(), 2 + typed as usize + 16);
+
+            let v1 = ApiV1::decode_raw_value(&b).unwrap();
+            assert_eq!(v1.user_value, v.user_value);
+            assert_eq!(v1.value_type, value_type);
+            assert_eq!(v1.status.contains(StatusCode::HAS_TYPE), typed);
+            assert!(v1.tombstone);
+            assert_eq!(ApiV1::encode_raw_value(v1), b);
+            let owned = RawValue {
+                tombstone: v.tombstone,
+                value_type,
+                ..RawValue::new(v.user_value.to_vec(), v.ts, v.status)
+            };
+            assert_eq!(ApiV1::encode_raw_value_owned(owned), b);
+        }
+
+        // Values written without a type tag are blobs.
+        let legacy = [&b"abc"[..], &[0; 8], &[0; 8]].concat();
+        let v = ApiV1::decode_raw_value(&legacy).unwrap();
+        assert_eq!(v.user_value, b"abc");
+        assert_eq!(v.value_type, ValueType::Blob);
+
+        let mut missing = vec![0; 8];
+        missing.encode_u64(StatusCode::HAS_TYPE.bits()).unwrap();
+        assert_eq!(ApiV1::decode_raw_value(&missing), None);
+    }
 }
@@ -0,0 +1,85 @@
+use crate::{ApiV1, DecodeError, RawValue, StatusCode, ValueType};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+/// The fields that are only encoded when flagged in the status.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OptionalFields {
+    pub value_type: ValueType,
+    pub refcount: Option<u32>,
+    pub deleted_size: Option<u64>,
+}
+
+impl ApiV1 {
+    /// The length of the optional fields flagged in `status`.
+    pub(crate) fn optional_fields_len(status: StatusCode) -> usize {
+        let mut len = 0;
+        if status.contains(StatusCode::HAS_TYPE) {
+            len += 1;
+        }
+        if status.contains(StatusCode::HAS_DELETED_SIZE) {
+            len += number::U64_SIZE;
+        }
+        if status.contains(StatusCode::HAS_REFCOUNT) {
+            len += number::U32_SIZE;
+        }
+        len
+    }
+
+    /// Appends the optional fields of `value` in the order `value_type ||
+    /// deleted_size || refcount`, the refcount last so it sits at a fixed
+    /// offset from the end of a trailer.
+    pub(crate) fn encode_optional_fields<T: AsRef<[u8]>>(buf: &mut Vec<u8>, value: &RawValue<T>) {
+        if value.value_type != ValueType::Blob {
+            buf.push(value.value_type.to_byte());
+        }
+        if let Some(deleted_size) = value.deleted_size {
+            buf.encode_u64(deleted_size).unwrap();
+        }
+        if let Some(refcount) = value.refcount {
+            buf.encode_u32(refcount).unwrap();
+        }
+    }
+
+    /// Decodes the optional fields flagged in `status` from `block`, which
+    /// must hold exactly those fields, reporting offsets into `block`.
+    pub(crate) fn decode_optional_fields(
+        block: &[u8],
+        status: StatusCode,
+    ) -> Result<OptionalFields, DecodeError> {
+        let eof = |rest: &[u8]| DecodeError::UnexpectedEOF {
+            offset: block.len() - rest.len(),
+        };
+        let mut rest = block;
+        let mut fields = OptionalFields::default();
+        if status.contains(StatusCode::HAS_TYPE) {
+            let (&ty, tail) = rest.split_first().ok_or_else(|| eof(rest))?;
+            fields.value_type = ValueType::from_byte(ty);
+            rest = tail;
+        }
+        if status.contains(StatusCode::HAS_DELETED_SIZE) {
+            let deleted_size = number::decode_u64(&mut rest).map_err(|_| eof(rest))?;
+            fields.deleted_size = Some(deleted_size);
+        }
+        if status.contains(StatusCode::HAS_REFCOUNT) {
+            let refcount = number::decode_u32(&mut rest).map_err(|_| eof(rest))?;
+            fields.refcount = Some(refcount);
+        }
+        debug_assert!(rest.is_empty());
+        Ok(fields)
+    }
+
+    /// Splits the optional fields flagged in `status` off the end of `rest`,
+    /// reporting offsets into `rest`.
+    pub(crate) fn split_optional_fields(
+        rest: &[u8],
+        status: StatusCode,
+    ) -> Result<(&[u8], OptionalFields), DecodeError> {
+        let len = rest
+            .len()
+            .checked_sub(Self::optional_fields_len(status))
+            .ok_or(DecodeError::UnexpectedEOF { offset: rest.len() })?;
+        let (rest, block) = rest.split_at(len);
+        let fields = Self::decode_optional_fields(block, status).map_err(|e| e.offset_by(len))?;
+        Ok((rest, fields))
+    }
+}
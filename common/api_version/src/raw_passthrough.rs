@@ -0,0 +1,59 @@
+use crate::{KvFormat, RawValue, StatusCode, ValueType};
+
+/// Untagged values with no ts/status trailer, e.g. imported third-party data.
+///
+/// Decoding treats the entire buffer as the user value and encoding writes
+/// only the user value, so ts, status, tombstone and value type are not
+/// preserved.
+#[derive(Default, Clone, Copy)]
+pub struct RawPassthrough;
+
+impl KvFormat for RawPassthrough {
+    fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+        Some(RawValue {
+            user_value: bytes,
+            ts: None,
+            status: StatusCode::GOOD,
+            tombstone: false,
+            value_type: ValueType::Blob,
+            refcount: None,
+            deleted_size: None,
+        })
+    }
+
+    fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+        value.user_value.to_vec()
+    }
+
+    fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+        value.user_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn passthrough_works() {
+        let all: Vec<u8> = (0..=255).collect();
+        for data in [&b""[..], b"123", &[0; 16], &all] {
+            let v = RawPassthrough::decode_raw_value(data).unwrap();
+            assert_eq!(v.user_value, data);
+            assert_eq!(v.ts, None);
+            assert_eq!(v.status, StatusCode::GOOD);
+            assert!(v.is_valid());
+
+            assert_eq!(RawPassthrough::encode_raw_value(v), data);
+            assert_eq!(
+                RawPassthrough::encode_raw_value_owned(RawValue::new(
+                    data.to_vec(),
+                    None,
+                    StatusCode::GOOD
+                )),
+                data
+            );
+        }
+    }
+}
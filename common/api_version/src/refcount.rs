@@ -0,0 +1,135 @@
+use crate::api_v1_magic::HEADER_SIZE;
+use crate::{ApiV1, ApiV1Magic, StatusCode};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+impl ApiV1 {
+    /// Increments the refcount of an encoded `ApiV1` value in place, returns
+    /// the new refcount, or `None` if the value has none or it would
+    /// overflow.
+    ///
+    /// `ApiV1Magic` values have their own `increment_refcount`, which checks
+    /// the header.
+    pub fn increment_refcount(encoded: &mut [u8]) -> Option<u32> {
+        Self::patch_refcount(encoded, |refcount| refcount.checked_add(1))
+    }
+
+    /// Decrements the refcount like `increment_refcount`, a result of
+    /// `Some(0)` means the last reference is gone.
+    pub fn decrement_refcount(encoded: &mut [u8]) -> Option<u32> {
+        Self::patch_refcount(encoded, |refcount| refcount.checked_sub(1))
+    }
+
+    fn patch_refcount(encoded: &mut [u8], f: impl FnOnce(u32) -> Option<u32>) -> Option<u32> {
+        let status_start = encoded.len().checked_sub(number::U64_SIZE)?;
+        let status = StatusCode::from(number::decode_u64(&mut &encoded[status_start..]).ok()?);
+        if !status.contains(StatusCode::HAS_REFCOUNT) {
+            return None;
+        }
+
+        // The refcount is the last optional field, right before the ts.
+        let end = status_start.checked_sub(number::U64_SIZE)?;
+        let start = end.checked_sub(number::U32_SIZE)?;
+        let mut slot = &mut encoded[start..end];
+        let refcount = f(number::decode_u32(&mut &*slot).ok()?)?;
+        slot.encode_u32(refcount).unwrap();
+        Some(refcount)
+    }
+}
+
+impl ApiV1Magic {
+    /// `ApiV1::increment_refcount` for a value with the magic header. Only
+    /// version 1 bodies are patched; an `ApiV1Compact` body, or a buffer
+    /// without the header, is left alone and yields `None`.
+    pub fn increment_refcount(encoded: &mut [u8]) -> Option<u32> {
+        ApiV1::increment_refcount(Self::v1_body(encoded)?)
+    }
+
+    /// `ApiV1::decrement_refcount` for a value with the magic header, with
+    /// the same checks as `increment_refcount`.
+    pub fn decrement_refcount(encoded: &mut [u8]) -> Option<u32> {
+        ApiV1::decrement_refcount(Self::v1_body(encoded)?)
+    }
+
+    fn v1_body(encoded: &mut [u8]) -> Option<&mut [u8]> {
+        if encoded.len() < HEADER_SIZE || encoded[..HEADER_SIZE - 1] != Self::MAGIC {
+            return None;
+        }
+        (encoded[HEADER_SIZE - 1] == Self::VERSION).then(|| &mut encoded[HEADER_SIZE..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiV1Compact, KvFormat, RawValue, ValueType};
+
+    fn shared(refcount: Option<u32>) -> RawValue<&'static [u8]> {
+        RawValue {
+            value_type: ValueType::Json,
+            refcount,
+            ..RawValue::new(&b"{}"[..], Some(9), StatusCode::from_user_status(1))
+        }
+    }
+
+    #[test]
+    fn refcount_roundtrip() {
+        for refcount in [None, Some(0), Some(1), Some(u32::MAX)] {
+            let v = shared(refcount);
+            let b = ApiV1::encode_raw_value(v);
+            assert_eq!(b.len(), v.encoded_len());
+            let v1 = ApiV1::decode_raw_value(&b).unwrap();
+            assert_eq!(v1.refcount, refcount);
+            assert_eq!(v1.value_type, ValueType::Json);
+            assert_eq!(v1.user_value, b"{}");
+
+            let m = ApiV1Magic::encode_raw_value(v);
+            assert_eq!(ApiV1Magic::decode_raw_value(&m).unwrap().refcount, refcount);
+        }
+    }
+
+    #[test]
+    fn refcount_in_place() {
+        let mut b = ApiV1::encode_raw_value(shared(Some(1)));
+        assert_eq!(ApiV1::increment_refcount(&mut b), Some(2));
+        assert_eq!(ApiV1::decode_raw_value(&b).unwrap().refcount, Some(2));
+        assert_eq!(ApiV1::decrement_refcount(&mut b), Some(1));
+        assert_eq!(ApiV1::decrement_refcount(&mut b), Some(0));
+        assert_eq!(ApiV1::decrement_refcount(&mut b), None);
+        let v = ApiV1::decode_raw_value(&b).unwrap();
+        assert_eq!(v.refcount, Some(0));
+        assert_eq!(v.user_value, b"{}");
+
+        let mut m = ApiV1Magic::encode_raw_value(shared(Some(u32::MAX - 1)));
+        assert_eq!(ApiV1Magic::increment_refcount(&mut m), Some(u32::MAX));
+        assert_eq!(ApiV1Magic::increment_refcount(&mut m), None);
+        assert_eq!(ApiV1Magic::decrement_refcount(&mut m), Some(u32::MAX - 1));
+        assert_eq!(
+            ApiV1Magic::decode_raw_value(&m).unwrap().refcount,
+            Some(u32::MAX - 1)
+        );
+
+        let mut plain = ApiV1::encode_raw_value(shared(None));
+        let before = plain.clone();
+        assert_eq!(ApiV1::increment_refcount(&mut plain), None);
+        assert_eq!(plain, before);
+        assert_eq!(ApiV1::increment_refcount(&mut []), None);
+        assert_eq!(ApiV1Magic::increment_refcount(&mut []), None);
+    }
+
+    #[test]
+    fn refcount_skips_compact_bodies() {
+        for refcount in [None, Some(1)] {
+            let mut c = ApiV1Compact::encode_raw_value(shared(refcount));
+            let before = c.clone();
+            assert_eq!(ApiV1Magic::increment_refcount(&mut c), None);
+            assert_eq!(ApiV1Magic::decrement_refcount(&mut c), None);
+            assert_eq!(c, before);
+        }
+
+        // A plain ApiV1 value lacks the header.
+        let mut plain = ApiV1::encode_raw_value(shared(Some(1)));
+        let before = plain.clone();
+        assert_eq!(ApiV1Magic::increment_refcount(&mut plain), None);
+        assert_eq!(plain, before);
+    }
+}
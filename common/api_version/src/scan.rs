@@ -0,0 +1,114 @@
+use std::marker::PhantomData;
+
+use crate::{DecodeError, KvFormat, RawValue};
+
+/// Decodes a scan one value at a time out of a single reusable buffer.
+///
+/// Each entry from `inner` is copied into the buffer and dropped before it
+/// is decoded, so the scan itself holds at most one encoded value no matter
+/// how long it is, and only allocates when the buffer has to grow. Whatever
+/// `inner` allocates to produce its entries is on top of that.
+///
+/// This can't be an `Iterator`: a value from `next_value` borrows the scan,
+/// and has to be dropped (or copied out) before the next call.
+pub struct BoundedScan<I, F> {
+    inner: I,
+    buf: Vec<u8>,
+    _format: PhantomData<F>,
+}
+
+impl<I, F> BoundedScan<I, F>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+    F: KvFormat,
+{
+    pub fn new(inner: I) -> Self {
+        BoundedScan {
+            inner,
+            buf: Vec::new(),
+            _format: PhantomData,
+        }
+    }
+
+    /// Decodes the next entry, returns `None` once `inner` is exhausted.
+    ///
+    /// Failures are reported as by `KvFormat::try_decode_raw_value`.
+    pub fn next_value(&mut self) -> Option<Result<RawValue<&[u8]>, DecodeError>> {
+        let entry = self.inner.next()?;
+        self.buf.clear();
+        self.buf.extend_from_slice(entry.as_ref());
+        drop(entry);
+        Some(F::try_decode_raw_value(&self.buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use cells_utils::testing::{allocs, CountingAlloc};
+
+    use super::*;
+    use crate::{ApiV1, StatusCode};
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    #[test]
+    fn bounded_scan() {
+        let encoded = (0..1000u64).map(|i| {
+            let user_value = vec![i as u8; (i % 64) as usize];
+            ApiV1::encode_raw_value(RawValue::new(&user_value, Some(i), StatusCode::GOOD))
+        });
+        let mut scan = BoundedScan::<_, ApiV1>::new(encoded);
+
+        let mut count = 0;
+        while let Some(value) = scan.next_value() {
+            let value = value.unwrap();
+            assert_eq!(value.ts, Some(count));
+            assert_eq!(value.user_value, vec![count as u8; (count % 64) as usize]);
+            count += 1;
+        }
+        assert_eq!(count, 1000);
+        // The buffer only ever grew to fit the largest entry.
+        assert!(scan.buf.capacity() < 2 * (63 + 16));
+        assert!(scan.next_value().is_none());
+    }
+
+    #[test]
+    fn bounded_scan_allocations() {
+        let encoded: Vec<Vec<u8>> = (0..1000u64)
+            .map(|i| {
+                let user_value = vec![i as u8; (i % 64) as usize];
+                ApiV1::encode_raw_value(RawValue::new(&user_value, Some(i), StatusCode::GOOD))
+            })
+            .collect();
+        let mut scan = BoundedScan::<_, ApiV1>::new(encoded.iter());
+
+        let before = allocs();
+        let mut count = 0;
+        while let Some(value) = scan.next_value() {
+            assert!(value.is_ok());
+            count += 1;
+        }
+        assert_eq!(count, 1000);
+        // Only the buffer growing to the largest entry allocates, not the
+        // number of entries.
+        assert!(allocs() - before <= 8);
+    }
+
+    #[test]
+    fn bounded_scan_corrupt_entry() {
+        let good = ApiV1::encode_raw_value(RawValue::new(&b"abc"[..], Some(1), StatusCode::GOOD));
+        let entries: [&[u8]; 3] = [&good, &[1, 2, 3], &good];
+        let mut scan = BoundedScan::<_, ApiV1>::new(entries.into_iter());
+
+        assert_eq!(scan.next_value().unwrap().unwrap().user_value, b"abc");
+        assert!(matches!(
+            scan.next_value(),
+            Some(Err(DecodeError::UnexpectedEOF { offset: 3 }))
+        ));
+        assert_eq!(scan.next_value().unwrap().unwrap().user_value, b"abc");
+        assert!(scan.next_value().is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use bitflags::bitflags;
+use std::error;
 use std::fmt::{self, Formatter};
 
 bitflags! {
@@ -8,8 +9,18 @@ bitflags! {
         const SYSTEM_STATUS_MASK = 0xff00_0000_0000_0000;
         // Mask for the bits section
         const USER_STATUS_MASK = 0x00ff_ffff_ffff_ffff;
+        // The top 8 user bits are free-form application flags, see
+        // `set_user_flag`, leaving the rest for a numeric user status.
+        const USER_FLAGS_MASK = 0x00ff_0000_0000_0000;
+        const USER_VALUE_MASK = 0x0000_ffff_ffff_ffff;
 
         const IS_TOMBSTONE                = 0x8000_0000_0000_0000;
+        // A `ValueType` byte precedes the ts
+        const HAS_TYPE                    = 0x4000_0000_0000_0000;
+        // A u32 refcount precedes the ts
+        const HAS_REFCOUNT                = 0x2000_0000_0000_0000;
+        // A u64 deleted size precedes the refcount
+        const HAS_DELETED_SIZE            = 0x1000_0000_0000_0000;
 
         const GOOD = 0;
     }
@@ -30,9 +41,140 @@ impl StatusCode {
         self.contains(StatusCode::IS_TOMBSTONE)
     }
 
-    /// Valid user status is 56 bytes, The first 8 bytes are system meta data
+    /// The numeric user status, without the application flags.
+    pub fn user_value(&self) -> u64 {
+        (*self & StatusCode::USER_VALUE_MASK).bits()
+    }
+
+    /// Keeps the larger of the two numeric user statuses, treating them as
+    /// severity levels, and the system bits and application flags of `self`.
+    pub fn merge_user_max(self, other: StatusCode) -> StatusCode {
+        let kept = self - StatusCode::USER_VALUE_MASK;
+        kept | StatusCode::from_user_status(self.user_value().max(other.user_value()))
+    }
+
+    /// Sets application flag `idx` in the `USER_FLAGS_MASK` region.
+    pub fn set_user_flag(&mut self, idx: u8) -> Result<(), UserFlagOutOfRange> {
+        *self |= Self::user_flag(idx)?;
+        Ok(())
+    }
+
+    /// Tests application flag `idx`, see `set_user_flag`.
+    pub fn get_user_flag(&self, idx: u8) -> Result<bool, UserFlagOutOfRange> {
+        Ok(self.contains(Self::user_flag(idx)?))
+    }
+
+    fn user_flag(idx: u8) -> Result<StatusCode, UserFlagOutOfRange> {
+        if idx >= 8 {
+            return Err(UserFlagOutOfRange { idx });
+        }
+        Ok(StatusCode {
+            bits: 1 << (StatusCode::USER_FLAGS_MASK.bits().trailing_zeros() + idx as u32),
+        })
+    }
+
+    /// A status with the numeric user status `value`, which is truncated to
+    /// the 48 bits of `USER_VALUE_MASK` so it can't set system bits or
+    /// application flags.
     pub fn from_user_status(value: u64) -> Self {
-        StatusCode { bits: value } & StatusCode::USER_STATUS_MASK
+        StatusCode { bits: value } & StatusCode::USER_VALUE_MASK
+    }
+
+    /// Like `from_user_status`, but returns `None` if `value` doesn't fit in
+    /// `USER_VALUE_MASK`.
+    pub fn try_from_user_status(value: u64) -> Option<Self> {
+        (value & !StatusCode::USER_VALUE_MASK.bits() == 0).then(|| StatusCode { bits: value })
+    }
+}
+
+/// A user flag index outside `0..8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserFlagOutOfRange {
+    pub idx: u8,
+}
+
+impl fmt::Display for UserFlagOutOfRange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "user flag index {} out of range", self.idx)
+    }
+}
+
+impl error::Error for UserFlagOutOfRange {}
+
+/// The system half of a `StatusCode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SystemStatus(StatusCode);
+
+impl SystemStatus {
+    pub fn bits(&self) -> u64 {
+        self.0.bits()
+    }
+
+    pub fn is_tombstone(&self) -> bool {
+        self.0.is_tombstone()
+    }
+}
+
+/// The user half of a `StatusCode`: a 48-bit numeric status and 8
+/// application flags. Statuses order by their numeric value first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UserStatus {
+    value: u64,
+    flags: u8,
+}
+
+impl UserStatus {
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The application flags, flag `idx` in bit `idx`.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+}
+
+impl StatusCode {
+    /// Splits the status into its system and user halves.
+    pub fn split(&self) -> (SystemStatus, UserStatus) {
+        (
+            SystemStatus(self.system_status()),
+            UserStatus {
+                value: self.user_value(),
+                flags: ((*self & StatusCode::USER_FLAGS_MASK).bits()
+                    >> StatusCode::USER_FLAGS_MASK.bits().trailing_zeros())
+                    as u8,
+            },
+        )
+    }
+}
+
+impl From<(SystemStatus, UserStatus)> for StatusCode {
+    fn from((system, user): (SystemStatus, UserStatus)) -> Self {
+        let flags = (user.flags as u64) << StatusCode::USER_FLAGS_MASK.bits().trailing_zeros();
+        system.0 | StatusCode::from_user_status(user.value) | StatusCode { bits: flags }
+    }
+}
+
+/// Serializes as the raw `u64` bits.
+#[cfg(feature = "serde")]
+impl serde::Serialize for StatusCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StatusCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <u64 as serde::Deserialize>::deserialize(deserializer).map(StatusCode::from)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for StatusCode {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(StatusCode::from(u64::arbitrary(u)?))
     }
 }
 
@@ -90,4 +232,96 @@ mod tests {
         let code: u64 = s.into();
         assert_eq!(code, 64);
     }
+
+    #[test]
+    fn status_split() {
+        let mut s = StatusCode::from_user_status(0x0000_cdef_0123_4567) | StatusCode::IS_TOMBSTONE;
+        s.set_user_flag(1).unwrap();
+        let (system, user) = s.split();
+        assert!(system.is_tombstone());
+        assert_eq!(system.bits(), 0x8000_0000_0000_0000);
+        assert_eq!(user.value(), 0x0000_cdef_0123_4567);
+        assert_eq!(user.flags(), 0b10);
+        assert_eq!(StatusCode::from((system, user)), s);
+
+        // The numeric value orders user statuses, whatever the flags.
+        let mut flagged = StatusCode::from_user_status(1);
+        flagged.set_user_flag(7).unwrap();
+        assert!(flagged.split().1 < StatusCode::from_user_status(2).split().1);
+
+        let (system, user) = StatusCode::GOOD.split();
+        assert!(!system.is_tombstone());
+        assert_eq!(user.value(), 0);
+        assert_eq!(StatusCode::from((system, user)), StatusCode::GOOD);
+    }
+
+    #[test]
+    fn status_merge_user_max() {
+        let low = StatusCode::from_user_status(3) | StatusCode::IS_TOMBSTONE;
+        let high = StatusCode::from_user_status(0x0000_ff00_0000_0001);
+
+        let merged = low.merge_user_max(high);
+        assert_eq!(merged.user_status(), high.user_status());
+        assert!(merged.is_tombstone());
+
+        let merged = high.merge_user_max(low);
+        assert_eq!(merged.user_status(), high.user_status());
+        assert!(!merged.is_tombstone());
+
+        assert_eq!(low.merge_user_max(StatusCode::GOOD), low);
+
+        // A flag doesn't make a lower severity win, and stays with `self`.
+        let mut flagged = StatusCode::from_user_status(3);
+        flagged.set_user_flag(7).unwrap();
+        let merged = flagged.merge_user_max(StatusCode::from_user_status(4));
+        assert_eq!(merged.user_value(), 4);
+        assert_eq!(merged.get_user_flag(7), Ok(true));
+        let merged = StatusCode::from_user_status(4).merge_user_max(flagged);
+        assert_eq!(merged, StatusCode::from_user_status(4));
+    }
+
+    #[test]
+    fn user_status_range() {
+        let max = StatusCode::USER_VALUE_MASK.bits();
+        assert_eq!(StatusCode::from_user_status(max).user_value(), max);
+        assert_eq!(StatusCode::from_user_status(1 << 48), StatusCode::GOOD);
+        assert_eq!(
+            StatusCode::from_user_status(u64::MAX) & StatusCode::USER_FLAGS_MASK,
+            StatusCode::GOOD
+        );
+
+        assert_eq!(
+            StatusCode::try_from_user_status(max),
+            Some(StatusCode::from_user_status(max))
+        );
+        assert_eq!(StatusCode::try_from_user_status(1 << 48), None);
+        assert_eq!(StatusCode::try_from_user_status(u64::MAX), None);
+    }
+
+    #[test]
+    fn user_flags() {
+        let value = 0x0000_1234_5678_9abc;
+        let mut s = StatusCode::from_user_status(value) | StatusCode::IS_TOMBSTONE;
+        for idx in [0, 3, 7] {
+            s.set_user_flag(idx).unwrap();
+        }
+
+        for idx in 0..8 {
+            assert_eq!(s.get_user_flag(idx), Ok(matches!(idx, 0 | 3 | 7)));
+        }
+        assert_eq!((s & StatusCode::USER_VALUE_MASK).bits(), value);
+        assert_eq!(
+            (s & StatusCode::USER_FLAGS_MASK).bits(),
+            0x0089_0000_0000_0000
+        );
+        assert!(s.is_tombstone());
+
+        assert_eq!(
+            StatusCode::GOOD.get_user_flag(8),
+            Err(UserFlagOutOfRange { idx: 8 })
+        );
+        let before = s;
+        assert_eq!(s.set_user_flag(8), Err(UserFlagOutOfRange { idx: 8 }));
+        assert_eq!(s, before);
+    }
 }
@@ -0,0 +1,82 @@
+use crate::{KvFormat, RawValue};
+
+/// Checks that re-encoding the decoded `value` reproduces its encoding with
+/// `F` byte for byte, describing the mismatch otherwise.
+pub fn assert_roundtrip_stable<F: KvFormat>(value: RawValue<&[u8]>) -> Result<(), String> {
+    let encoded = F::encode_raw_value(value);
+    let decoded = F::decode_raw_value(&encoded)
+        .ok_or_else(|| format!("failed to decode {:?} encoded as {:02x?}", value, encoded))?;
+    let reencoded = F::encode_raw_value(decoded);
+    if reencoded != encoded {
+        return Err(format!(
+            "{:?} encoded as {:02x?} but re-encoded as {:02x?}",
+            value, encoded, reencoded
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{ApiV1, StatusCode};
+
+    #[test]
+    fn api_v1_roundtrip_stable() {
+        let statuses = [
+            StatusCode::GOOD,
+            StatusCode::from_user_status(42),
+            StatusCode::USER_STATUS_MASK,
+            StatusCode::IS_TOMBSTONE,
+        ];
+        for status in statuses {
+            for tombstone in [false, true] {
+                for ts in [None, Some(0), Some(u64::MAX)] {
+                    for user_value in [&b""[..], b"value"] {
+                        let value = RawValue {
+                            tombstone,
+                            ..RawValue::new(user_value, ts, status)
+                        };
+                        assert_roundtrip_stable::<ApiV1>(value).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_mismatch_is_reported() {
+        /// Only records whether the ts is set, and always decodes one.
+        #[derive(Clone, Copy)]
+        struct Lossy;
+
+        impl KvFormat for Lossy {
+            fn decode_raw_value(bytes: &[u8]) -> Option<RawValue<&[u8]>> {
+                Some(RawValue::new(
+                    bytes.split_last()?.1,
+                    Some(0),
+                    StatusCode::GOOD,
+                ))
+            }
+
+            fn encode_raw_value(value: RawValue<&[u8]>) -> Vec<u8> {
+                [value.user_value, &[value.ts.is_some() as u8]].concat()
+            }
+
+            fn encode_raw_value_owned(value: RawValue<Vec<u8>>) -> Vec<u8> {
+                Self::encode_raw_value(RawValue {
+                    tombstone: value.tombstone,
+                    ..RawValue::new(&value.user_value, value.ts, value.status)
+                })
+            }
+        }
+
+        let value = RawValue::new(&b"ab"[..], Some(7), StatusCode::GOOD);
+        assert_eq!(assert_roundtrip_stable::<Lossy>(value), Ok(()));
+
+        let err = assert_roundtrip_stable::<Lossy>(RawValue { ts: None, ..value }).unwrap_err();
+        assert!(err.contains("[61, 62, 00]"), "{}", err);
+        assert!(err.contains("[61, 62, 01]"), "{}", err);
+    }
+}
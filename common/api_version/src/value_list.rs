@@ -0,0 +1,58 @@
+use crate::{KvFormat, RawValue};
+use cells_utils::codec::number::{self, NumberEncoder};
+
+/// Encodes `values` with `F`, framing each sub-value with a varint length.
+pub fn encode_value_list<F: KvFormat>(values: &[RawValue<&[u8]>]) -> Vec<u8> {
+    let mut buf = vec![];
+    for value in values {
+        let encoded = F::encode_raw_value(*value);
+        buf.encode_var_u64(encoded.len() as u64).unwrap();
+        buf.extend_from_slice(&encoded);
+    }
+    buf
+}
+
+/// Decodes a list written by `encode_value_list`, the sub-values borrow from
+/// `bytes`.
+pub fn decode_value_list<F: KvFormat>(bytes: &[u8]) -> Option<Vec<RawValue<&[u8]>>> {
+    let mut data = bytes;
+    let mut values = vec![];
+    while !data.is_empty() {
+        let len = number::decode_var_u64(&mut data).ok()? as usize;
+        if data.len() < len {
+            return None;
+        }
+        let (encoded, rest) = data.split_at(len);
+        values.push(F::decode_raw_value(encoded)?);
+        data = rest;
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{ApiV1, StatusCode};
+
+    #[test]
+    fn value_list_works() {
+        let values = [
+            RawValue::new(&b"first"[..], Some(1), StatusCode::GOOD),
+            RawValue::new(&b""[..], Some(2), StatusCode::IS_TOMBSTONE),
+            RawValue::new(&b"third"[..], Some(3), StatusCode::from_user_status(9)),
+        ];
+
+        let b = encode_value_list::<ApiV1>(&values);
+        let decoded = decode_value_list::<ApiV1>(&b).unwrap();
+        assert_eq!(decoded, values);
+        assert!(decoded[1].tombstone);
+
+        // Sub-values are borrowed from the input.
+        let range = b.as_ptr_range();
+        assert!(range.contains(&decoded[0].user_value.as_ptr()));
+
+        assert_eq!(decode_value_list::<ApiV1>(&[]), Some(vec![]));
+        assert_eq!(decode_value_list::<ApiV1>(&b[..b.len() - 1]), None);
+    }
+}
@@ -0,0 +1,93 @@
+/// The logical type of a user value, stored as a byte when the status has
+/// `HAS_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Blob,
+    Json,
+    Counter,
+    /// A tag written by a newer version of the crate, kept so that
+    /// re-encoding writes it back unchanged. Never one of the tags above.
+    Unknown(u8),
+}
+
+impl ValueType {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            ValueType::Blob => 0,
+            ValueType::Json => 1,
+            ValueType::Counter => 2,
+            ValueType::Unknown(b) => b,
+        }
+    }
+
+    pub fn from_byte(b: u8) -> ValueType {
+        match b {
+            0 => ValueType::Blob,
+            1 => ValueType::Json,
+            2 => ValueType::Counter,
+            b => ValueType::Unknown(b),
+        }
+    }
+}
+
+/// Serializes as the stored tag byte.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValueType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.to_byte())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ValueType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <u8 as serde::Deserialize>::deserialize(deserializer).map(ValueType::from_byte)
+    }
+}
+
+/// Values without a type tag are blobs.
+impl Default for ValueType {
+    fn default() -> Self {
+        ValueType::Blob
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn value_type_byte() {
+        for ty in [
+            ValueType::Blob,
+            ValueType::Json,
+            ValueType::Counter,
+            ValueType::Unknown(3),
+            ValueType::Unknown(0xff),
+        ] {
+            assert_eq!(ValueType::from_byte(ty.to_byte()), ty);
+        }
+        assert_eq!(ValueType::from_byte(3), ValueType::Unknown(3));
+        assert_eq!(ValueType::Unknown(3).to_byte(), 3);
+        assert_eq!(ValueType::default(), ValueType::Blob);
+    }
+
+    #[test]
+    fn unknown_tag_survives_reencoding() {
+        use crate::{ApiV1, KvFormat, RawValue, StatusCode};
+
+        let b = ApiV1::encode_raw_value(RawValue {
+            value_type: ValueType::from_byte(3),
+            ..RawValue::new(&b"v"[..], Some(1), StatusCode::GOOD)
+        });
+        // `v || type || ts || status`
+        assert_eq!(b[1], 3);
+        let canonical = ApiV1::canonical_encoding(&b).unwrap();
+        assert_eq!(canonical, b);
+        assert_eq!(
+            ApiV1::decode_raw_value(&canonical).unwrap().value_type,
+            ValueType::Unknown(3)
+        );
+    }
+}
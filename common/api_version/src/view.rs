@@ -0,0 +1,98 @@
+use std::cell::Cell;
+
+use crate::{ApiV1, StatusCode};
+use cells_utils::codec::number;
+
+/// An encoded `ApiV1` value whose fields are only decoded when asked for.
+///
+/// Creating the view reads the status, which is needed to know where the
+/// user value ends; the ts is decoded on first use and cached.
+#[derive(Debug, Clone)]
+pub struct ValueView<'a> {
+    bytes: &'a [u8],
+    status: StatusCode,
+    ts: Cell<Option<u64>>,
+}
+
+impl ApiV1 {
+    /// Wraps `bytes` in a `ValueView`, or returns `None` wherever
+    /// `decode_raw_value` would.
+    pub fn view(bytes: &[u8]) -> Option<ValueView<'_>> {
+        let mut status_slice = &bytes[bytes.len().checked_sub(number::U64_SIZE)?..];
+        let status = StatusCode::from(number::decode_u64(&mut status_slice).unwrap_or_default());
+        let trailer_len = 2 * number::U64_SIZE + Self::optional_fields_len(status);
+        if bytes.len() < trailer_len {
+            return None;
+        }
+        Some(ValueView {
+            bytes,
+            status,
+            ts: Cell::new(None),
+        })
+    }
+}
+
+impl<'a> ValueView<'a> {
+    pub fn user_value(&self) -> &'a [u8] {
+        let len = self.bytes.len() - 2 * number::U64_SIZE - ApiV1::optional_fields_len(self.status);
+        &self.bytes[..len]
+    }
+
+    pub fn ts(&self) -> Option<u64> {
+        if let Some(ts) = self.ts.get() {
+            return Some(ts);
+        }
+        let start = self.bytes.len() - 2 * number::U64_SIZE;
+        let mut ts_slice = &self.bytes[start..start + number::U64_SIZE];
+        let ts = number::decode_u64(&mut ts_slice).unwrap_or_default();
+        self.ts.set(Some(ts));
+        Some(ts)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn tombstone(&self) -> bool {
+        self.status.is_tombstone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{KvFormat, RawValue, ValueType};
+
+    #[test]
+    fn view_matches_decode() {
+        let values = [
+            RawValue::new(&b"hello"[..], Some(1654045749000), StatusCode::GOOD),
+            RawValue::new(&b""[..], Some(7), StatusCode::IS_TOMBSTONE),
+            RawValue {
+                value_type: ValueType::Json,
+                refcount: Some(3),
+                deleted_size: Some(40),
+                ..RawValue::new(&b"{}"[..], Some(9), StatusCode::from_user_status(5))
+            },
+        ];
+        for v in values {
+            let b = ApiV1::encode_raw_value(v);
+            let decoded = ApiV1::decode_raw_value(&b).unwrap();
+            let view = ApiV1::view(&b).unwrap();
+            assert_eq!(view.status(), decoded.status);
+            assert_eq!(view.tombstone(), decoded.tombstone);
+            assert_eq!(view.user_value(), decoded.user_value);
+            assert_eq!(view.ts(), decoded.ts);
+            // Served from the cache the second time.
+            assert_eq!(view.ts(), decoded.ts);
+
+            for len in 0..b.len() {
+                assert_eq!(
+                    ApiV1::view(&b[..len]).is_none(),
+                    ApiV1::decode_raw_value(&b[..len]).is_none()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use cells_utils::codec::number::{self, Error, NumberEncoder};
+
+use crate::Key;
+
+/// Builds a block of sorted entries up to a target size, e.g. for bulk
+/// loading SST-like files.
+///
+/// Each entry is `shared || unshared || value_len` as varints, followed by
+/// the key bytes not shared with the previous key and the value.
+pub struct BlockBuilder {
+    target_size: usize,
+    buf: Vec<u8>,
+    last_key: Vec<u8>,
+}
+
+impl BlockBuilder {
+    pub fn new(target_size: usize) -> Self {
+        BlockBuilder {
+            target_size,
+            buf: Vec::with_capacity(target_size),
+            last_key: vec![],
+        }
+    }
+
+    /// Appends an entry, returns false and leaves the block unchanged if it
+    /// would grow past the target size. The first entry is always added.
+    ///
+    /// Keys must be added in ascending order.
+    pub fn add(&mut self, key: &Key, value: &[u8]) -> bool {
+        let key = key.as_slice();
+        debug_assert!(self.buf.is_empty() || key > &self.last_key[..]);
+        let shared = self
+            .last_key
+            .iter()
+            .zip(key)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let len = self.buf.len();
+        self.buf.encode_var_u64(shared as u64).unwrap();
+        self.buf
+            .encode_var_u64((key.len() - shared) as u64)
+            .unwrap();
+        self.buf.encode_var_u64(value.len() as u64).unwrap();
+        self.buf.extend_from_slice(&key[shared..]);
+        self.buf.extend_from_slice(value);
+        if len > 0 && self.buf.len() > self.target_size {
+            self.buf.truncate(len);
+            return false;
+        }
+
+        self.last_key.truncate(shared);
+        self.last_key.extend_from_slice(&key[shared..]);
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// The size of the block so far.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Decodes the entries of a block written by `BlockBuilder`, in order.
+pub fn decode_block(mut data: &[u8]) -> number::Result<Vec<(Key, Vec<u8>)>> {
+    let mut entries = vec![];
+    let mut key: Vec<u8> = vec![];
+    while !data.is_empty() {
+        let shared = number::decode_var_u64(&mut data)? as usize;
+        let unshared = number::decode_var_u64(&mut data)? as usize;
+        let value_len = number::decode_var_u64(&mut data)? as usize;
+        if shared > key.len() || data.len() < unshared.saturating_add(value_len) {
+            return Err(Error::EncoderUnexpectedEOF);
+        }
+
+        let (suffix, rest) = data.split_at(unshared);
+        let (value, rest) = rest.split_at(value_len);
+        key.truncate(shared);
+        key.extend_from_slice(suffix);
+        entries.push((Key::from_raw(&key), value.to_vec()));
+        data = rest;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn block_roundtrip() {
+        let entries: Vec<(Key, Vec<u8>)> = (0..100u32)
+            .map(|i| {
+                let key = Key::from_raw(format!("table_row_{:04}", i).as_bytes());
+                (key, vec![i as u8; (i % 5) as usize])
+            })
+            .collect();
+
+        let mut builder = BlockBuilder::new(4096);
+        for (key, value) in &entries {
+            assert!(builder.add(key, value));
+        }
+        let block = builder.finish();
+        // Every key after the first shares at least "table_row_0".
+        let raw_len: usize = entries.iter().map(|(k, v)| k.len() + v.len()).sum();
+        assert!(block.len() < raw_len);
+
+        let decoded = decode_block(&block).unwrap();
+        assert_eq!(decoded.len(), entries.len());
+        for ((key, value), (k, v)) in decoded.iter().zip(&entries) {
+            assert_eq!(key, k);
+            assert_eq!(value, v);
+        }
+    }
+
+    #[test]
+    fn block_full() {
+        let mut builder = BlockBuilder::new(16);
+        assert!(builder.add(&Key::from_raw(b"a"), &[0; 32]));
+        assert!(!builder.add(&Key::from_raw(b"b"), b""));
+        let len = builder.len();
+
+        let mut builder = BlockBuilder::new(len + 8);
+        assert!(builder.is_empty());
+        assert!(builder.add(&Key::from_raw(b"a"), &[0; 32]));
+        assert!(builder.add(&Key::from_raw(b"ab"), b""));
+        assert!(!builder.add(&Key::from_raw(b"b"), b"x"));
+        // A rejected entry doesn't affect the prefix of the next one.
+        assert!(builder.add(&Key::from_raw(b"ac"), b""));
+
+        let decoded = decode_block(&builder.finish()).unwrap();
+        let keys: Vec<_> = decoded.into_iter().map(|(k, _)| k.into_raw()).collect();
+        assert_eq!(keys, [&b"a"[..], b"ab", b"ac"]);
+    }
+
+    #[test]
+    fn block_truncated() {
+        let mut builder = BlockBuilder::new(64);
+        builder.add(&Key::from_raw(b"abc"), b"value");
+        let block = builder.finish();
+        assert!(decode_block(&block[..block.len() - 1]).is_err());
+        assert!(decode_block(&[1, 0, 0]).is_err());
+        assert!(decode_block(&[]).unwrap().is_empty());
+    }
+}
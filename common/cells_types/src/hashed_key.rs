@@ -0,0 +1,103 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::Key;
+
+/// A `Key` with its hash computed once, for keys looked up repeatedly in hot
+/// loops.
+///
+/// `Hash` feeds the cached hash instead of rehashing the bytes, and `Eq`
+/// only compares the bytes when the hashes match.
+#[derive(Debug, Clone)]
+pub struct HashedKey {
+    key: Key,
+    hash: u64,
+}
+
+impl HashedKey {
+    pub fn new(key: Key) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        HashedKey {
+            key,
+            hash: hasher.finish(),
+        }
+    }
+
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    pub fn into_key(self) -> Key {
+        self.key
+    }
+
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl From<Key> for HashedKey {
+    fn from(key: Key) -> Self {
+        HashedKey::new(key)
+    }
+}
+
+impl PartialEq for HashedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.key == other.key
+    }
+}
+
+impl Eq for HashedKey {}
+
+impl Hash for HashedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn hashed_key_consistent() {
+        let a = HashedKey::new(Key::from_raw(b"table_row_1"));
+        let b = HashedKey::from(Key::from_raw(b"table_row_1"));
+        let c = HashedKey::new(Key::from_raw(b"table_row_2"));
+        assert_eq!(a, b);
+        assert_eq!(a.hash_value(), b.hash_value());
+        assert_ne!(a, c);
+
+        let mut map = HashMap::new();
+        map.insert(a, 1);
+        map.insert(c, 2);
+        assert_eq!(map.get(&b), Some(&1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(b.into_key().into_raw(), b"table_row_1");
+    }
+
+    #[test]
+    fn hashed_key_collision() {
+        let a = HashedKey {
+            key: Key::from_raw(b"a"),
+            hash: 7,
+        };
+        let b = HashedKey {
+            key: Key::from_raw(b"b"),
+            hash: 7,
+        };
+        assert_ne!(a, b);
+
+        let mut map = HashMap::new();
+        map.insert(a.clone(), 1);
+        map.insert(b.clone(), 2);
+        assert_eq!(map.get(&a), Some(&1));
+        assert_eq!(map.get(&b), Some(&2));
+    }
+}
@@ -0,0 +1,54 @@
+use cells_utils::codec::number::{self, Error, NumberEncoder};
+
+use crate::Key;
+
+/// Appends a secondary index entry: the primary `keys` it points back to,
+/// each framed as `len || key` with a varint length.
+pub fn encode_index_entry(buf: &mut Vec<u8>, keys: &[Key]) {
+    for key in keys {
+        buf.encode_var_u64(key.len() as u64).unwrap();
+        buf.extend_from_slice(key.as_slice());
+    }
+}
+
+/// Decodes the primary keys of an index entry written by
+/// `encode_index_entry`, in order.
+pub fn decode_index_entry(mut data: &[u8]) -> number::Result<Vec<Key>> {
+    let mut keys = vec![];
+    while !data.is_empty() {
+        let len = number::decode_var_u64(&mut data)?;
+        if (data.len() as u64) < len {
+            return Err(Error::EncoderUnexpectedEOF);
+        }
+        let (key, rest) = data.split_at(len as usize);
+        keys.push(Key::from_raw(key));
+        data = rest;
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn index_entry_roundtrip() {
+        let keys = vec![
+            Key::from_raw(b"row_1"),
+            Key::from_raw(b""),
+            Key::from_raw(&[0, 0, 1, 0]),
+            Key::from_raw(&[0xff; 200]),
+        ];
+        let mut buf = vec![];
+        encode_index_entry(&mut buf, &keys);
+        assert_eq!(decode_index_entry(&buf).unwrap(), keys);
+
+        let mut empty = vec![];
+        encode_index_entry(&mut empty, &[]);
+        assert!(decode_index_entry(&empty).unwrap().is_empty());
+
+        assert!(decode_index_entry(&buf[..buf.len() - 1]).is_err());
+        assert!(decode_index_entry(&[5, b'a']).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+
+use crate::Key;
+
+/// Full outer joins two scans sorted by key, yielding each key once with the
+/// value from either side it is present on.
+pub fn merge_join<'a, L, R>(
+    left: L,
+    right: R,
+) -> impl Iterator<Item = (Key, Option<&'a [u8]>, Option<&'a [u8]>)>
+where
+    L: IntoIterator<Item = (Key, &'a [u8])>,
+    R: IntoIterator<Item = (Key, &'a [u8])>,
+{
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    std::iter::from_fn(move || {
+        let order = match (left.peek(), right.peek()) {
+            (Some((l, _)), Some((r, _))) => l.cmp(r),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        Some(match order {
+            Ordering::Less => {
+                let (key, value) = left.next()?;
+                (key, Some(value), None)
+            }
+            Ordering::Greater => {
+                let (key, value) = right.next()?;
+                (key, None, Some(value))
+            }
+            Ordering::Equal => {
+                let (key, l) = left.next()?;
+                let (_, r) = right.next()?;
+                (key, Some(l), Some(r))
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    type Row<'a> = (Vec<u8>, Option<&'a [u8]>, Option<&'a [u8]>);
+
+    fn join<'a>(left: &[(&[u8], &'a [u8])], right: &[(&[u8], &'a [u8])]) -> Vec<Row<'a>> {
+        let scan = |side: &[(&[u8], &'a [u8])]| -> Vec<(Key, &'a [u8])> {
+            side.iter().map(|(k, v)| (Key::from_raw(k), *v)).collect()
+        };
+        merge_join(scan(left), scan(right))
+            .map(|(k, l, r)| (k.into_raw(), l, r))
+            .collect()
+    }
+
+    #[test]
+    fn merge_join_overlapping() {
+        let rows = join(&[(b"a", b"1"), (b"b", b"2")], &[(b"a", b"x"), (b"b", b"y")]);
+        assert_eq!(
+            rows,
+            vec![
+                (b"a".to_vec(), Some(&b"1"[..]), Some(&b"x"[..])),
+                (b"b".to_vec(), Some(&b"2"[..]), Some(&b"y"[..])),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_join_partial() {
+        let rows = join(
+            &[(b"a", b"1"), (b"c", b"3"), (b"d", b"4")],
+            &[(b"b", b"y"), (b"c", b"z")],
+        );
+        assert_eq!(
+            rows,
+            vec![
+                (b"a".to_vec(), Some(&b"1"[..]), None),
+                (b"b".to_vec(), None, Some(&b"y"[..])),
+                (b"c".to_vec(), Some(&b"3"[..]), Some(&b"z"[..])),
+                (b"d".to_vec(), Some(&b"4"[..]), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_join_disjoint() {
+        let rows = join(&[(b"c", b"3")], &[(b"a", b"x"), (b"b", b"y")]);
+        assert_eq!(
+            rows,
+            vec![
+                (b"a".to_vec(), None, Some(&b"x"[..])),
+                (b"b".to_vec(), None, Some(&b"y"[..])),
+                (b"c".to_vec(), Some(&b"3"[..]), None),
+            ]
+        );
+        assert!(join(&[], &[]).is_empty());
+    }
+}
@@ -1,5 +1,15 @@
+use std::{convert::Infallible, error, fmt, ops::Bound, str::FromStr};
+
+use cells_utils::codec::{bytes, number};
+use smallvec::SmallVec;
+
+/// Keys up to this many bytes are stored inline, without a heap allocation.
+pub const INLINE_KEY_LEN: usize = 32;
+
+type KeyBuf = SmallVec<[u8; INLINE_KEY_LEN]>;
+
 #[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone)]
-pub struct Key(Vec<u8>);
+pub struct Key(KeyBuf);
 
 /// Value type which is essentially raw bytes.
 pub type Value = Vec<u8>;
@@ -12,29 +22,1245 @@ pub type KvPair = (Vec<u8>, Value);
 impl Key {
     #[inline]
     pub fn from_raw(key: &[u8]) -> Key {
-        Key(key.to_vec())
+        Key(KeyBuf::from_slice(key))
+    }
+
+    /// An empty key with room for `cap` bytes, to build up with `push` and
+    /// `extend_from_slice`.
+    #[inline]
+    pub fn from_raw_with_capacity(cap: usize) -> Key {
+        Key(KeyBuf::with_capacity(cap))
+    }
+
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.0.push(byte);
     }
 
     #[inline]
-    pub fn as_raw(&self) -> &Vec<u8> {
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// The exclusive end key of a scan over `prefix`, `None` if the scan
+    /// runs to the end of the keyspace. See `prefix_next`.
+    pub fn prefix_upper_bound(prefix: &[u8]) -> Option<Key> {
+        let end = Key::from_raw(prefix).prefix_next();
+        (!end.is_empty()).then(|| end)
+    }
+
+    /// The smallest `total_len`-byte key starting with `prefix`, i.e. the
+    /// prefix padded with `0x00`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is longer than `total_len`.
+    pub fn prefix_lower_bound(prefix: &[u8], total_len: usize) -> Key {
+        Key::padded(prefix, total_len, 0)
+    }
+
+    /// The largest `total_len`-byte key starting with `prefix`, i.e. the
+    /// prefix padded with `0xff`. Unlike `prefix_upper_bound` it is
+    /// inclusive, and only bounds keys of that fixed length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is longer than `total_len`.
+    pub fn prefix_upper_bound_padded(prefix: &[u8], total_len: usize) -> Key {
+        Key::padded(prefix, total_len, 0xff)
+    }
+
+    /// `prefix` padded with `pad` to `total_len` bytes, panicking if it is
+    /// already longer.
+    fn padded(prefix: &[u8], total_len: usize, pad: u8) -> Key {
+        assert!(
+            prefix.len() <= total_len,
+            "prefix of {} bytes exceeds key length {}",
+            prefix.len(),
+            total_len
+        );
+        let mut raw = KeyBuf::from_slice(prefix);
+        raw.resize(total_len, pad);
+        Key(raw)
+    }
+
+    /// The raw bytes, a slice since short keys don't live in a `Vec`; use
+    /// `to_vec` or `into_raw` where one is needed.
+    #[inline]
+    pub fn as_raw(&self) -> &[u8] {
         &self.0
     }
 
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Compares the raw key bytes with `other` without building a `Key`.
+    #[inline]
+    pub fn eq_slice(&self, other: &[u8]) -> bool {
+        self.0[..] == *other
+    }
+
+    #[inline]
+    pub fn as_key_ref(&self) -> KeyRef<'_> {
+        KeyRef(&self.0)
+    }
+
     #[inline]
     pub fn into_raw(self) -> Vec<u8> {
+        self.0.into_vec()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The bytes the key allocated on the heap, which is the buffer capacity
+    /// once the key outgrew `INLINE_KEY_LEN` and zero before. The inline
+    /// bytes are part of `size_of::<Key>()` and not counted.
+    pub fn heap_size(&self) -> usize {
+        if self.0.spilled() {
+            self.0.capacity()
+        } else {
+            0
+        }
+    }
+
+    /// Returns the memcomparable encoding of the key, which sorts the same
+    /// way as the raw bytes even when concatenated with other fields.
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(bytes::encoded_bytes_len(self.0.len()));
+        bytes::encode_bytes(&mut buf, &self.0);
+        buf
+    }
+
+    /// Decodes a key written by `encoded`, any trailing bytes are ignored.
+    pub fn from_encoded(encoded: &[u8]) -> number::Result<Key> {
+        bytes::decode_bytes(&mut &*encoded).map(Key::from)
+    }
+
+    /// Best-effort guess whether the key is the output of `encoded`: it must
+    /// be whole groups of 9 bytes with valid markers and zero padding. A raw
+    /// key that happens to have that shape, such as 8 zero bytes followed by
+    /// `0xf7`, is a false positive.
+    pub fn looks_encoded(&self) -> bool {
+        let mut data = self.as_slice();
+        bytes::decode_bytes(&mut data).is_ok() && data.is_empty()
+    }
+
+    /// Escapes the key as printable ASCII: `\\` and `\"` for backslash and
+    /// double quote, `\xNN` for non-printable bytes.
+    pub fn to_escaped_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Parses the output of `to_escaped_string`.
+    pub fn from_escaped(s: &str) -> Result<Key, EscapeError> {
+        let s = s.as_bytes();
+        let mut raw = Vec::with_capacity(s.len());
+        let mut i = 0;
+        while i < s.len() {
+            if s[i] != b'\\' {
+                raw.push(s[i]);
+                i += 1;
+                continue;
+            }
+            let err = EscapeError { index: i };
+            match s.get(i + 1) {
+                Some(b'\\') | Some(b'"') => {
+                    raw.push(s[i + 1]);
+                    i += 2;
+                }
+                Some(b'x') => {
+                    let hex = s.get(i + 2..i + 4).ok_or(err)?;
+                    let hex = std::str::from_utf8(hex).map_err(|_| err)?;
+                    if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(err);
+                    }
+                    raw.push(u8::from_str_radix(hex, 16).map_err(|_| err)?);
+                    i += 4;
+                }
+                _ => return Err(err),
+            }
+        }
+        Ok(Key::from(raw))
+    }
+
+    /// Lowercase hex of the raw bytes, without separators.
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Parses the output of `to_hex`, either case is accepted.
+    pub fn from_hex(s: &str) -> Result<Key, HexError> {
+        if s.len() % 2 != 0 {
+            return Err(HexError::OddLength { len: s.len() });
+        }
+        let digit = |index: usize| {
+            let c = s.as_bytes()[index];
+            (c as char)
+                .to_digit(16)
+                .map(|d| d as u8)
+                .ok_or(HexError::InvalidDigit { index })
+        };
+        (0..s.len())
+            .step_by(2)
+            .map(|i| Ok(digit(i)? << 4 | digit(i + 1)?))
+            .collect::<Result<_, _>>()
+            .map(Key)
+    }
+
+    /// Shortens the key to at most `len` bytes.
+    pub fn truncate(mut self, len: usize) -> Key {
+        self.0.truncate(len);
+        self
+    }
+
+    /// Replaces a leading `old` with `new`, returns `None` if the key doesn't
+    /// start with `old`.
+    pub fn remap_prefix(&self, old: &[u8], new: &[u8]) -> Option<Key> {
+        let suffix = self.0.strip_prefix(old)?;
+        let mut raw = KeyBuf::with_capacity(new.len() + suffix.len());
+        raw.extend_from_slice(new);
+        raw.extend_from_slice(suffix);
+        Some(Key(raw))
+    }
+
+    #[inline]
+    pub fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.0.starts_with(prefix)
+    }
+
+    /// Removes a leading `prefix`, returns `None` if the key doesn't start
+    /// with it.
+    pub fn strip_prefix(&self, prefix: &[u8]) -> Option<Key> {
+        self.0.strip_prefix(prefix).map(Key::from_raw)
+    }
+
+    /// Peels a `width`-byte big-endian keyspace id off the front of the key,
+    /// returns `None` if the key is shorter than `width` or `width` doesn't
+    /// fit in a u32.
+    pub fn split_keyspace(&self, width: usize) -> Option<(u32, Key)> {
+        if width > 4 || self.0.len() < width {
+            return None;
+        }
+        let (id, user_key) = self.0.split_at(width);
+        let id = id.iter().fold(0u32, |id, &b| id << 8 | b as u32);
+        Some((id, Key::from_raw(user_key)))
+    }
+
+    /// Prefixes `user_key` with `id` as `width` big-endian bytes, the
+    /// inverse of `split_keyspace`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is more than 4 or `id` doesn't fit in `width`
+    /// bytes.
+    pub fn with_keyspace(id: u32, width: usize, user_key: &[u8]) -> Key {
+        assert!(width <= 4, "keyspace width {} exceeds 4 bytes", width);
+        assert!(
+            width == 4 || id >> (8 * width) == 0,
+            "keyspace id {} doesn't fit in {} bytes",
+            id,
+            width
+        );
+        let mut raw = KeyBuf::with_capacity(width + user_key.len());
+        raw.extend_from_slice(&id.to_be_bytes()[4 - width..]);
+        raw.extend_from_slice(user_key);
+        Key(raw)
+    }
+
+    /// Builds a time-series key of the `bucket_ms` window holding `ts`,
+    /// big-endian so windows sort chronologically, followed by `series`.
+    pub fn with_time_bucket(bucket_ms: u64, ts: u64, series: &[u8]) -> Key {
+        assert!(bucket_ms > 0, "time bucket must be positive");
+        let mut raw = KeyBuf::with_capacity(number::U64_SIZE + series.len());
+        raw.extend_from_slice(&(ts / bucket_ms).to_be_bytes());
+        raw.extend_from_slice(series);
+        Key(raw)
+    }
+
+    /// Reads the window written by `with_time_bucket`.
+    pub fn time_bucket(&self) -> Option<u64> {
+        let bucket = self.0.get(..number::U64_SIZE)?;
+        Some(u64::from_be_bytes(bucket.try_into().unwrap()))
+    }
+
+    /// Appends an MVCC commit `ts`, stored descending so that newer versions
+    /// of a user key sort first.
+    pub fn append_ts(mut self, ts: u64) -> Key {
+        self.0.extend_from_slice(&(!ts).to_be_bytes());
+        self
+    }
+
+    /// Reads the ts written by `append_ts`.
+    pub fn decode_ts(&self) -> number::Result<u64> {
+        let start = self
+            .0
+            .len()
+            .checked_sub(number::U64_SIZE)
+            .ok_or(number::Error::EncoderUnexpectedEOF)?;
+        number::decode_u64(&mut &self.0[start..]).map(|ts| !ts)
+    }
+
+    /// Strips the ts written by `append_ts`, leaving the user key, or returns
+    /// `None` if the key is too short to hold a ts, like `decode_ts`.
+    pub fn truncate_ts(&self) -> Option<Key> {
+        let len = self.0.len().checked_sub(number::U64_SIZE)?;
+        Some(Key::from_raw(&self.0[..len]))
+    }
+
+    /// Splits a RocksDB user-timestamp key into the user key and its trailing
+    /// 8-byte ts, which RocksDB encodes little-endian.
+    pub fn from_rocksdb_with_ts(encoded: &[u8]) -> Option<(Key, u64)> {
+        let split = encoded.len().checked_sub(8)?;
+        let (key, ts) = encoded.split_at(split);
+        Some((Key::from_raw(key), u64::from_le_bytes(ts.try_into().ok()?)))
+    }
+
+    /// Appends `ts` the way RocksDB's user-timestamp API expects it.
+    pub fn to_rocksdb_with_ts(&self, ts: u64) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(self.0.len() + 8);
+        encoded.extend_from_slice(&self.0);
+        encoded.extend_from_slice(&ts.to_le_bytes());
+        encoded
+    }
+
+    /// Returns the smallest key greater than this one.
+    pub fn next(&self) -> Key {
+        let mut raw = KeyBuf::with_capacity(self.0.len() + 1);
+        raw.extend_from_slice(&self.0);
+        raw.push(0);
+        Key(raw)
+    }
+
+    /// Returns the smallest key greater than every key starting with this
+    /// one, i.e. the exclusive end of the prefix range.
+    ///
+    /// There is no such key if this one is empty or all `0xff`, in which case
+    /// the empty key is returned as the unbounded end.
+    pub fn prefix_next(&self) -> Key {
+        let mut raw = self.0.clone();
+        while let Some(last) = raw.pop() {
+            if last != 0xff {
+                raw.push(last + 1);
+                break;
+            }
+        }
+        Key(raw)
+    }
+
+    /// Returns the smallest key greater than this one, unless it falls
+    /// outside `end`.
+    pub fn successor_in_range(&self, end: &Bound<Key>) -> Option<Key> {
+        let next = self.next();
+
+        let in_range = match end {
+            Bound::Included(end) => next <= *end,
+            Bound::Excluded(end) => next < *end,
+            Bound::Unbounded => true,
+        };
+        in_range.then(|| next)
+    }
+
+    /// Returns the `index`th segment of a key laid out as consecutive
+    /// fixed-length segments of `lengths`, or `None` if `index` is out of
+    /// range or the key is shorter than the whole layout.
+    pub fn segment_at(&self, index: usize, lengths: &[usize]) -> Option<&[u8]> {
+        let total = lengths
+            .iter()
+            .try_fold(0usize, |total, len| total.checked_add(*len))?;
+        if index >= lengths.len() || self.0.len() < total {
+            return None;
+        }
+        let start: usize = lengths[..index].iter().sum();
+        Some(&self.0[start..start + lengths[index]])
+    }
+
+    /// Matches the key against `pattern`, where a `wildcard` byte matches
+    /// any single byte, except as the last byte of `pattern`, where it
+    /// matches any suffix, including an empty one.
+    ///
+    /// Every other pattern byte must match exactly, so a literal `wildcard`
+    /// byte in the key can only be matched by a wildcard.
+    pub fn matches_pattern(&self, pattern: &Key, wildcard: u8) -> bool {
+        let mut key = self.0.as_slice();
+        let mut pattern = pattern.0.as_slice();
+        if let Some((&last, head)) = pattern.split_last() {
+            if last == wildcard {
+                if key.len() < head.len() {
+                    return false;
+                }
+                key = &key[..head.len()];
+                pattern = head;
+            }
+        }
+
+        key.len() == pattern.len()
+            && key
+                .iter()
+                .zip(pattern)
+                .all(|(&k, &p)| p == wildcard || k == p)
+    }
+}
+
+/// Takes over the buffer of keys too long to be stored inline.
+impl From<Vec<u8>> for Key {
+    fn from(raw: Vec<u8>) -> Self {
+        Key(KeyBuf::from_vec(raw))
+    }
+}
+
+impl From<&[u8]> for Key {
+    fn from(raw: &[u8]) -> Self {
+        Key::from_raw(raw)
+    }
+}
+
+impl PartialEq<[u8]> for Key {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.eq_slice(other)
+    }
+}
+
+/// Parses the string's UTF-8 bytes as the raw key.
+impl FromStr for Key {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Key::from_raw(s.as_bytes()))
+    }
+}
+
+/// Renders the key as `to_escaped_string` does, so binary keys are always
+/// safe to log.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.as_key_ref(), f)
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A borrowed key, for reading raw keys without cloning them.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+pub struct KeyRef<'a>(&'a [u8]);
+
+impl<'a> KeyRef<'a> {
+    #[inline]
+    pub fn from_raw(key: &'a [u8]) -> KeyRef<'a> {
+        KeyRef(key)
+    }
+
+    #[inline]
+    pub fn as_raw(&self) -> &'a [u8] {
         self.0
     }
+
+    pub fn to_key(&self) -> Key {
+        Key::from_raw(self.0)
+    }
+}
+
+impl fmt::Display for KeyRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &b in self.0 {
+            match b {
+                b'\\' => write!(f, "\\\\")?,
+                b'"' => write!(f, "\\\"")?,
+                _ if b.is_ascii_graphic() || b == b' ' => write!(f, "{}", b as char)?,
+                _ => write!(f, "\\x{:02x}", b)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for KeyRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Serializes as a byte string rather than a sequence of integers.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Key {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_bytes::serialize(self.as_slice(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Key {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_bytes::deserialize(deserializer).map(|raw: Vec<u8>| Key::from(raw))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Key {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Key::from(Vec::<u8>::arbitrary(u)?))
+    }
+}
+
+/// The longest suffix `enumerate_prefix` enumerates, 2^32 keys.
+pub const MAX_ENUMERATE_SUFFIX_LEN: usize = 4;
+
+/// `enumerate_prefix` was asked for a suffix longer than
+/// `MAX_ENUMERATE_SUFFIX_LEN`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuffixTooLong {
+    pub len: usize,
+}
+
+impl fmt::Display for SuffixTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "suffix of {} bytes exceeds the limit {}",
+            self.len, MAX_ENUMERATE_SUFFIX_LEN
+        )
+    }
+}
+
+impl error::Error for SuffixTooLong {}
+
+/// `Key::from_escaped` found a malformed escape sequence starting at `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeError {
+    pub index: usize,
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid escape sequence at index {}", self.index)
+    }
+}
+
+impl error::Error for EscapeError {}
+
+/// Why `Key::from_hex` rejected its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    /// The input has an odd number of digits.
+    OddLength { len: usize },
+    /// The byte at `index` is not a hex digit.
+    InvalidDigit { index: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HexError::OddLength { len } => write!(f, "odd number of hex digits: {}", len),
+            HexError::InvalidDigit { index } => write!(f, "invalid hex digit at index {}", index),
+        }
+    }
+}
+
+impl error::Error for HexError {}
+
+/// Lazily yields `prefix || suffix` for every `suffix_len`-byte suffix, in
+/// key order.
+pub fn enumerate_prefix(
+    prefix: &[u8],
+    suffix_len: usize,
+) -> Result<impl Iterator<Item = Key>, SuffixTooLong> {
+    if suffix_len > MAX_ENUMERATE_SUFFIX_LEN {
+        return Err(SuffixTooLong { len: suffix_len });
+    }
+    let prefix = prefix.to_vec();
+    Ok((0..1u64 << (8 * suffix_len)).map(move |i| {
+        let mut raw = KeyBuf::with_capacity(prefix.len() + suffix_len);
+        raw.extend_from_slice(&prefix);
+        raw.extend_from_slice(&i.to_be_bytes()[8 - suffix_len..]);
+        Key(raw)
+    }))
+}
+
+/// Returns the bytes shared by the start of every key in `keys`.
+pub fn longest_common_prefix(keys: &[Key]) -> Vec<u8> {
+    let (first, rest) = match keys.split_first() {
+        Some(split) => split,
+        None => return vec![],
+    };
+    let len = rest.iter().fold(first.0.len(), |len, key| {
+        first.0[..len]
+            .iter()
+            .zip(key.0.iter())
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    first.0[..len].to_vec()
+}
+
+/// Collapses runs of equal keys in a sorted batch, keeping the last value of
+/// each run.
+pub fn coalesce_last_wins(pairs: Vec<KvPair>) -> Vec<KvPair> {
+    let mut out: Vec<KvPair> = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs {
+        match out.last_mut() {
+            Some(last) if last.0 == key => last.1 = value,
+            _ => out.push((key, value)),
+        }
+    }
+    out
+}
+
+/// The first key breaking the order of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortError {
+    /// `keys[index]` equals the key before it.
+    Duplicate { index: usize },
+    /// `keys[index]` sorts before the key before it.
+    OutOfOrder { index: usize },
+}
+
+impl SortError {
+    pub fn index(&self) -> usize {
+        match *self {
+            SortError::Duplicate { index } | SortError::OutOfOrder { index } => index,
+        }
+    }
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortError::Duplicate { index } => write!(f, "duplicate key at index {}", index),
+            SortError::OutOfOrder { index } => write!(f, "out of order key at index {}", index),
+        }
+    }
+}
+
+impl error::Error for SortError {}
+
+/// Checks that `keys` is sorted, strictly increasing if `strict` is set,
+/// otherwise non-decreasing.
+pub fn assert_sorted_keys(keys: &[Key], strict: bool) -> Result<(), SortError> {
+    for (i, pair) in keys.windows(2).enumerate() {
+        let index = i + 1;
+        if pair[1] < pair[0] {
+            return Err(SortError::OutOfOrder { index });
+        }
+        if strict && pair[1] == pair[0] {
+            return Err(SortError::Duplicate { index });
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
 
+    use cells_utils::testing::{allocs, CountingAlloc};
+
     use super::*;
 
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
     #[test]
     fn key_works() {
         let result = b"";
         assert_eq!(Key::from_raw(result).into_raw(), b"");
         assert_eq!(Key::from_raw(b"a").into_raw(), b"a");
+        assert!(Key::from_raw(b"").is_empty());
+        assert_eq!(Key::from_raw(b"abc").len(), 3);
+        assert_eq!(Key::from_raw(b"abc").as_slice(), b"abc");
+    }
+
+    #[test]
+    fn encoded_roundtrip() {
+        let raws: Vec<&[u8]> = vec![
+            b"",
+            b"\x00",
+            b"\x00\x00",
+            b"\x00\xff",
+            b"\xff",
+            b"\xff\x00",
+            b"\xff\xff\xff\xff\xff\xff\xff\xff",
+            b"\xff\xff\xff\xff\xff\xff\xff\xff\x00",
+            b"a",
+            b"a\x00",
+            b"a\x00b",
+            b"ab",
+            b"abcdefgh",
+            b"abcdefgh\x00",
+            b"abcdefghi",
+        ];
+        for a in &raws {
+            let key = Key::from_raw(a);
+            assert_eq!(Key::from_encoded(&key.encoded()).unwrap().into_raw(), *a);
+            for b in &raws {
+                let (ea, eb) = (key.encoded(), Key::from_raw(b).encoded());
+                assert_eq!(a.cmp(b), ea.cmp(&eb), "{:?} vs {:?}", a, b);
+            }
+        }
+
+        for a in &raws {
+            assert!(Key::from_raw(&Key::from_raw(a).encoded()).looks_encoded());
+        }
+        for raw in [&b""[..], b"a", b"table_row", &[0xff; 9], b"abcdefgh\xff"] {
+            assert!(!Key::from_raw(raw).looks_encoded(), "{:?}", raw);
+        }
+        let mut trailing = Key::from_raw(b"a").encoded();
+        trailing.push(0);
+        assert!(!Key::from_raw(&trailing).looks_encoded());
+
+        // Trailing fields don't affect the decoded key or its order.
+        let mut a = Key::from_raw(b"a").encoded();
+        let mut b = Key::from_raw(b"a\x00").encoded();
+        a.push(0xff);
+        b.push(0x00);
+        assert!(a < b);
+        assert_eq!(Key::from_encoded(&a).unwrap().into_raw(), b"a");
+        assert!(Key::from_encoded(b"a").is_err());
+    }
+
+    #[test]
+    fn key_display() {
+        assert_eq!(Key::from_raw(b"").to_string(), "");
+        assert_eq!(Key::from_raw(b"t_row 1").to_string(), "t_row 1");
+        assert_eq!(
+            Key::from_raw(b"a\x00\xff\n\\\"").to_string(),
+            r#"a\x00\xff\x0a\\\""#
+        );
+        // Invalid UTF-8 is escaped rather than lossily replaced.
+        assert_eq!(format!("{:?}", Key::from_raw(&[0xc3, 0x28])), "\\xc3(");
+    }
+
+    #[test]
+    fn prefix_upper_bound() {
+        let bound = |prefix: &[u8]| Key::prefix_upper_bound(prefix).map(Key::into_raw);
+        assert_eq!(bound(b"a"), Some(b"b".to_vec()));
+        assert_eq!(bound(b"a\xff"), Some(b"b".to_vec()));
+        assert_eq!(bound(b"a\x00"), Some(b"a\x01".to_vec()));
+        assert_eq!(bound(b"\xff\xff"), None);
+        assert_eq!(bound(b""), None);
+    }
+
+    #[test]
+    fn prefix_padded_bounds() {
+        for prefix in [&b""[..], b"\x00", b"\x7f", b"\xff", b"\x12\x34"] {
+            let lower = Key::prefix_lower_bound(prefix, 2);
+            let upper = Key::prefix_upper_bound_padded(prefix, 2);
+            assert_eq!((lower.len(), upper.len()), (2, 2));
+            for key in enumerate_prefix(b"", 2).unwrap() {
+                assert_eq!(
+                    lower <= key && key <= upper,
+                    key.starts_with(prefix),
+                    "{:?} in {:?}..={:?}",
+                    key,
+                    lower,
+                    upper
+                );
+            }
+        }
+        assert_eq!(Key::prefix_lower_bound(b"ab", 4).as_raw(), b"ab\x00\x00");
+        assert_eq!(
+            Key::prefix_upper_bound_padded(b"ab", 4).as_raw(),
+            b"ab\xff\xff"
+        );
+    }
+
+    #[test]
+    fn key_escaped() {
+        assert_eq!(Key::from_raw(b"").to_escaped_string(), "");
+        assert!(Key::from_escaped("").unwrap().is_empty());
+
+        let cases: [&[u8]; 6] = [
+            b"plain key",
+            b"\\",
+            b"\"quoted\"",
+            b"\\x41",
+            b"\x00\x7f\x80\xff",
+            b"\\\\\"\\",
+        ];
+        for raw in cases {
+            let escaped = Key::from_raw(raw).to_escaped_string();
+            assert!(escaped.bytes().all(|b| b.is_ascii_graphic() || b == b' '));
+            assert_eq!(Key::from_escaped(&escaped).unwrap().as_slice(), raw);
+        }
+        assert_eq!(Key::from_raw(b"\\x41").to_escaped_string(), r"\\x41");
+        assert_eq!(Key::from_escaped(r"\x41\x4A").unwrap().as_slice(), b"AJ");
+
+        for bad in [r"\", r"a\q", r"\x4", r"\xg0", r"\x+1"] {
+            assert!(Key::from_escaped(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(Key::from_escaped(r"ab\"), Err(EscapeError { index: 2 }));
+    }
+
+    #[test]
+    fn key_hex() {
+        assert_eq!(Key::from_raw(b"").to_hex(), "");
+        assert!(Key::from_hex("").unwrap().is_empty());
+
+        let key = Key::from_raw(b"\x00\x01a\xff\x00");
+        assert_eq!(key.to_hex(), "000161ff00");
+        assert_eq!(Key::from_hex(&key.to_hex()).unwrap(), key);
+        assert_eq!(Key::from_hex("000161FF00").unwrap(), key);
+
+        assert_eq!(Key::from_hex("abc"), Err(HexError::OddLength { len: 3 }));
+        assert_eq!(
+            Key::from_hex("0g"),
+            Err(HexError::InvalidDigit { index: 1 })
+        );
+        // A multi-byte character is reported, not split.
+        assert_eq!(
+            Key::from_hex("aéb"),
+            Err(HexError::InvalidDigit { index: 1 })
+        );
+        assert_eq!(
+            Key::from_hex("+1"),
+            Err(HexError::InvalidDigit { index: 0 })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn key_serde() {
+        let key = Key::from_raw(b"a\x00\xff");
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(serde_json::from_str::<Key>(&json).unwrap(), key);
+        let bin = bincode::serialize(&key).unwrap();
+        assert_eq!(bin.len(), 8 + 3);
+        assert_eq!(bincode::deserialize::<Key>(&bin).unwrap(), key);
+    }
+
+    #[test]
+    fn key_conversions() {
+        assert_eq!("abc".parse::<Key>().unwrap(), Key::from_raw(b"abc"));
+        assert_eq!("".parse::<Key>().unwrap(), Key::from_raw(b""));
+        assert_eq!(Key::try_from(&b"abc"[..]).unwrap(), Key::from_raw(b"abc"));
+        assert_eq!(Key::from(b"abc".to_vec()), Key::from_raw(b"abc"));
+
+        let raw = vec![7; INLINE_KEY_LEN + 1];
+        let ptr = raw.as_ptr();
+        let key = Key::from(raw);
+        assert_eq!(key.as_raw().as_ptr(), ptr);
+        assert_eq!(key.into_raw().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn key_ref() {
+        let key = Key::from_raw(b"a\x00\"");
+        let key_ref = key.as_key_ref();
+        assert_eq!(key_ref.as_raw().as_ptr(), key.as_raw().as_ptr());
+        assert_eq!(key_ref, KeyRef::from_raw(b"a\x00\""));
+        assert_eq!(key_ref.to_key(), key);
+        assert_eq!(format!("{:?}", key_ref), key.to_escaped_string());
+        assert!(KeyRef::from_raw(b"a") < KeyRef::from_raw(b"b"));
+    }
+
+    #[test]
+    fn key_builder() {
+        let mut key = Key::from_raw_with_capacity(13);
+        assert!(key.is_empty());
+        key.extend_from_slice(b"prefix");
+        key.push(b'/');
+        key.extend_from_slice(b"suffix");
+        assert_eq!(key, Key::from_raw(b"prefix/suffix"));
+    }
+
+    #[test]
+    fn key_inline() {
+        for len in [
+            0,
+            1,
+            INLINE_KEY_LEN - 1,
+            INLINE_KEY_LEN,
+            INLINE_KEY_LEN + 1,
+            100,
+        ] {
+            let raw: Vec<u8> = (0..len as u8).collect();
+            let before = allocs();
+            let key = Key::from_raw(&raw);
+            assert_eq!(key.0.spilled(), len > INLINE_KEY_LEN);
+            // Only a spilled key allocates, once.
+            assert_eq!(allocs() - before, (len > INLINE_KEY_LEN) as usize);
+            assert_eq!(key.as_raw(), &raw[..]);
+
+            // Keys built by appending compare equal to `from_raw` whether
+            // or not they spilled on the way.
+            let mut expected = raw.clone();
+            expected.push(0);
+            let next = key.next();
+            assert_eq!(next.0.spilled(), len + 1 > INLINE_KEY_LEN);
+            assert_eq!(next, Key::from_raw(&expected));
+            assert_eq!(key.into_raw(), raw);
+        }
+
+        let short = Key::from_raw(b"abc");
+        let long = Key::from_raw(&[b'a'; INLINE_KEY_LEN + 1]);
+        assert_eq!(short.heap_size(), 0);
+        assert!(long.heap_size() > INLINE_KEY_LEN);
+        let mut grown = Key::from_raw_with_capacity(100);
+        grown.push(0);
+        assert!(grown.heap_size() >= 100);
+        assert!(short > long);
+        assert!(long.clone().truncate(3) < short);
+        assert_eq!(long.truncate(1), Key::from_raw(b"a"));
+    }
+
+    #[test]
+    fn truncate() {
+        let key = Key::from_raw(b"table_row");
+        assert_eq!(key.clone().truncate(0).into_raw(), b"");
+        assert_eq!(key.clone().truncate(5).into_raw(), b"table");
+        assert_eq!(key.clone().truncate(9).into_raw(), b"table_row");
+        assert_eq!(key.truncate(100).into_raw(), b"table_row");
+    }
+
+    #[test]
+    fn strip_prefix() {
+        let k = Key::from_raw(b"t1_row");
+        assert!(k.starts_with(b"t1_"));
+        assert!(k.starts_with(b""));
+        assert!(!k.starts_with(b"t2_"));
+        assert_eq!(
+            k.strip_prefix(b"t1_").map(Key::into_raw),
+            Some(b"row".to_vec())
+        );
+        assert!(k.strip_prefix(b"t1_row").unwrap().is_empty());
+        assert!(k.strip_prefix(b"t2_").is_none());
+        assert!(k.strip_prefix(b"t1_row_").is_none());
+    }
+
+    #[test]
+    fn keyspace() {
+        let k = Key::with_keyspace(0x0a0b0c, 3, b"row");
+        assert_eq!(k.as_slice(), b"\x0a\x0b\x0crow");
+        let (id, user_key) = k.split_keyspace(3).unwrap();
+        assert_eq!((id, user_key.into_raw()), (0x0a0b0c, b"row".to_vec()));
+
+        let k = Key::with_keyspace(u32::MAX, 4, b"row");
+        let (id, user_key) = k.split_keyspace(4).unwrap();
+        assert_eq!((id, user_key.into_raw()), (u32::MAX, b"row".to_vec()));
+
+        // A key holding only the keyspace id has an empty user key.
+        for width in [3, 4] {
+            let k = Key::with_keyspace(7, width, b"");
+            assert_eq!(k.len(), width);
+            let (id, user_key) = k.split_keyspace(width).unwrap();
+            assert_eq!(id, 7);
+            assert!(user_key.is_empty());
+            assert!(Key::from_raw(&k.as_slice()[1..])
+                .split_keyspace(width)
+                .is_none());
+        }
+        assert!(Key::from_raw(b"longer than four")
+            .split_keyspace(5)
+            .is_none());
+    }
+
+    #[test]
+    fn time_bucket() {
+        let a = Key::with_time_bucket(60_000, 120_000, b"cpu");
+        let b = Key::with_time_bucket(60_000, 179_999, b"mem");
+        let c = Key::with_time_bucket(60_000, 180_000, b"cpu");
+        assert_eq!(a.time_bucket(), Some(2));
+        assert_eq!(b.time_bucket(), Some(2));
+        assert_eq!(c.time_bucket(), Some(3));
+        assert_eq!(a.as_raw()[..8], b.as_raw()[..8]);
+        assert!(b < c);
+        assert!(Key::with_time_bucket(1, 255, b"") < Key::with_time_bucket(1, 256, b""));
+        assert_eq!(
+            a.strip_prefix(&2u64.to_be_bytes()),
+            Some(Key::from_raw(b"cpu"))
+        );
+        assert_eq!(Key::from_raw(b"short").time_bucket(), None);
+    }
+
+    #[test]
+    fn remap_prefix() {
+        let k = Key::from_raw(b"t1_row");
+        let remap = |old: &[u8], new: &[u8]| k.remap_prefix(old, new).map(Key::into_raw);
+
+        assert_eq!(remap(b"t1", b"t2"), Some(b"t2_row".to_vec()));
+        assert_eq!(remap(b"t1", b"t1000"), Some(b"t1000_row".to_vec()));
+        assert_eq!(remap(b"t1_", b""), Some(b"row".to_vec()));
+        assert_eq!(remap(b"", b"x"), Some(b"xt1_row".to_vec()));
+        assert_eq!(remap(b"t1_row", b"t2"), Some(b"t2".to_vec()));
+
+        assert_eq!(remap(b"t2", b"t3"), None);
+        assert_eq!(remap(b"t1_row_", b"t3"), None);
+    }
+
+    #[test]
+    fn enumerate_prefix_works() {
+        let keys: Vec<_> = enumerate_prefix(b"p", 1).unwrap().collect();
+        assert_eq!(keys.len(), 256);
+        assert_eq!(keys[0].as_raw(), b"p\x00");
+        assert_eq!(keys[255].as_raw(), b"p\xff");
+        assert!(assert_sorted_keys(&keys, true).is_ok());
+
+        let keys: Vec<_> = enumerate_prefix(b"ab", 2).unwrap().collect();
+        assert_eq!(keys.len(), 1 << 16);
+        assert_eq!(keys[1].as_raw(), b"ab\x00\x01");
+        assert_eq!(keys[256].as_raw(), b"ab\x01\x00");
+        assert_eq!(keys[(1 << 16) - 1].as_raw(), b"ab\xff\xff");
+        assert!(assert_sorted_keys(&keys, true).is_ok());
+        let end = Key::from_raw(b"ab").prefix_next();
+        assert!(keys
+            .iter()
+            .all(|k| k.as_raw().starts_with(b"ab") && *k < end));
+
+        assert_eq!(enumerate_prefix(b"ab", 0).unwrap().count(), 1);
+        let mut keys = enumerate_prefix(b"", 4).unwrap();
+        assert_eq!(keys.size_hint(), (1 << 32, Some(1 << 32)));
+        assert_eq!(keys.next().unwrap().as_raw(), b"\x00\x00\x00\x00");
+        assert_eq!(
+            enumerate_prefix(b"", 5).err(),
+            Some(SuffixTooLong { len: 5 })
+        );
+    }
+
+    #[test]
+    fn longest_common_prefix_works() {
+        let keys: Vec<_> = [
+            &b"tenant_1/table/row_01"[..],
+            b"tenant_1/table/row_02",
+            b"tenant_1/table/r",
+        ]
+        .iter()
+        .map(|k| Key::from_raw(k))
+        .collect();
+        assert_eq!(longest_common_prefix(&keys), b"tenant_1/table/r");
+
+        let keys = [
+            Key::from_raw(b"abc"),
+            Key::from_raw(b"xyz"),
+            Key::from_raw(b"abd"),
+        ];
+        assert_eq!(longest_common_prefix(&keys), b"");
+
+        assert_eq!(longest_common_prefix(&[Key::from_raw(b"abc")]), b"abc");
+        assert_eq!(longest_common_prefix(&[]), b"");
+    }
+
+    #[test]
+    fn coalesce_last_wins_works() {
+        let pair = |k: &[u8], v: &[u8]| (k.to_vec(), v.to_vec());
+        let pairs = vec![
+            pair(b"a", b"1"),
+            pair(b"b", b"1"),
+            pair(b"b", b"2"),
+            pair(b"c", b"1"),
+            pair(b"d", b"1"),
+            pair(b"d", b"2"),
+            pair(b"d", b"3"),
+            pair(b"e", b"1"),
+        ];
+
+        assert_eq!(
+            coalesce_last_wins(pairs),
+            vec![
+                pair(b"a", b"1"),
+                pair(b"b", b"2"),
+                pair(b"c", b"1"),
+                pair(b"d", b"3"),
+                pair(b"e", b"1"),
+            ]
+        );
+        assert!(coalesce_last_wins(vec![]).is_empty());
+    }
+
+    #[test]
+    fn segment_at() {
+        let key = Key::from_raw(b"t1tblrow42");
+        let layout = [2, 3, 5];
+        assert_eq!(key.segment_at(0, &layout), Some(&b"t1"[..]));
+        assert_eq!(key.segment_at(1, &layout), Some(&b"tbl"[..]));
+        assert_eq!(key.segment_at(2, &layout), Some(&b"row42"[..]));
+        assert_eq!(key.segment_at(3, &layout), None);
+        assert_eq!(key.segment_at(0, &[]), None);
+
+        // The whole layout must fit, even for an earlier segment.
+        assert_eq!(key.segment_at(0, &[2, 3, 6]), None);
+        assert_eq!(Key::from_raw(b"").segment_at(0, &[0]), Some(&b""[..]));
+        assert_eq!(key.segment_at(0, &[2, usize::MAX]), None);
+    }
+
+    #[test]
+    fn matches_pattern() {
+        let matches =
+            |k: &[u8], p: &[u8]| Key::from_raw(k).matches_pattern(&Key::from_raw(p), b'*');
+
+        // Exact.
+        assert!(matches(b"abc", b"abc"));
+        assert!(matches(b"", b""));
+        assert!(!matches(b"abc", b"abd"));
+        assert!(!matches(b"abc", b"ab"));
+        assert!(!matches(b"ab", b"abc"));
+
+        // Single byte.
+        assert!(matches(b"abc", b"a*c"));
+        assert!(matches(b"a*c", b"a*c"));
+        assert!(!matches(b"ac", b"a*c"));
+        assert!(!matches(b"abbc", b"a*c"));
+
+        // Trailing suffix.
+        assert!(matches(b"abc", b"ab*"));
+        assert!(matches(b"ab", b"ab*"));
+        assert!(matches(b"abcdef", b"a*c*"));
+        assert!(matches(b"", b"*"));
+        assert!(matches(b"anything", b"*"));
+        assert!(!matches(b"a", b"ab*"));
+        assert!(!matches(b"axc", b"ab*"));
+    }
+
+    #[test]
+    fn sorted_keys() {
+        let keys = |raw: &[&[u8]]| raw.iter().map(|k| Key::from_raw(k)).collect::<Vec<_>>();
+
+        let sorted = keys(&[b"", b"a", b"a\x00", b"b"]);
+        assert_eq!(assert_sorted_keys(&sorted, true), Ok(()));
+        assert_eq!(assert_sorted_keys(&[], true), Ok(()));
+
+        let dup = keys(&[b"a", b"b", b"b", b"c"]);
+        assert_eq!(
+            assert_sorted_keys(&dup, true),
+            Err(SortError::Duplicate { index: 2 })
+        );
+        assert_eq!(assert_sorted_keys(&dup, false), Ok(()));
+
+        let unordered = keys(&[b"a", b"c", b"c", b"b"]);
+        let err = assert_sorted_keys(&unordered, false).unwrap_err();
+        assert_eq!(err, SortError::OutOfOrder { index: 3 });
+        assert_eq!(err.index(), 3);
+        assert_eq!(
+            assert_sorted_keys(&unordered, true),
+            Err(SortError::Duplicate { index: 2 })
+        );
+    }
+
+    #[test]
+    fn next() {
+        for raw in [&b""[..], b"a", b"a\x00", b"a\xff", b"\xff\xff"] {
+            let key = Key::from_raw(raw);
+            let next = key.next();
+            assert!(key < next);
+            // Any key greater than `key` is at least `next`.
+            for other in [&b"\x00"[..], b"a", b"a\x00\x00", b"a\x01", b"\xff\xff\x00"] {
+                let other = Key::from_raw(other);
+                assert!(other <= key || other >= next);
+            }
+        }
+        assert_eq!(Key::from_raw(b"").next().into_raw(), b"\x00");
+    }
+
+    #[test]
+    fn prefix_next() {
+        let cases: [(&[u8], &[u8]); 6] = [
+            (b"abc", b"abd"),
+            (b"ab\xff", b"ac"),
+            (b"a\xff\xff", b"b"),
+            (b"\x00", b"\x01"),
+            (b"\xff\xff", b""),
+            (b"", b""),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(Key::from_raw(raw).prefix_next().into_raw(), expected);
+        }
+
+        let prefix = Key::from_raw(b"ab\xff");
+        let end = prefix.prefix_next();
+        for raw in [&b"ab\xff"[..], b"ab\xff\xff\xff", b"ab\xff\x00"] {
+            assert!(Key::from_raw(raw) < end);
+        }
+        assert!(Key::from_raw(b"ac") >= end);
+    }
+
+    #[test]
+    fn successor_in_range() {
+        let k = Key::from_raw(b"a");
+        let succ = |end: Bound<Key>| k.successor_in_range(&end).map(Key::into_raw);
+        let key = Key::from_raw;
+
+        assert_eq!(succ(Bound::Unbounded), Some(b"a\x00".to_vec()));
+        assert_eq!(succ(Bound::Excluded(key(b"b"))), Some(b"a\x00".to_vec()));
+        assert_eq!(
+            succ(Bound::Included(key(b"a\x00"))),
+            Some(b"a\x00".to_vec())
+        );
+        assert_eq!(succ(Bound::Excluded(key(b"a\x00"))), None);
+        assert_eq!(succ(Bound::Included(key(b"a"))), None);
+        assert_eq!(succ(Bound::Excluded(key(b"a"))), None);
+    }
+
+    #[test]
+    fn mvcc_ts() {
+        for ts in [0, 1, 1654045749000, u64::MAX] {
+            let key = Key::from_raw(b"user").append_ts(ts);
+            assert_eq!(key.as_raw().len(), 4 + 8);
+            assert_eq!(key.decode_ts().unwrap(), ts);
+            assert_eq!(key.truncate_ts().unwrap().into_raw(), b"user");
+        }
+
+        let older = Key::from_raw(b"user").append_ts(5);
+        let newer = Key::from_raw(b"user").append_ts(6);
+        assert!(newer < older);
+        assert!(Key::from_raw(b"user").append_ts(0) < Key::from_raw(b"usex").append_ts(u64::MAX));
+
+        let short = Key::from_raw(b"1234567");
+        assert!(matches!(
+            short.decode_ts(),
+            Err(number::Error::EncoderUnexpectedEOF)
+        ));
+        assert_eq!(short.truncate_ts(), None);
+        let ts_only = Key::from_raw(b"").append_ts(1);
+        assert_eq!(ts_only.truncate_ts(), Some(Key::from_raw(b"")));
+    }
+
+    #[test]
+    fn rocksdb_with_ts() {
+        let k = Key::from_raw(b"key");
+        let encoded = k.to_rocksdb_with_ts(0x0102_0304_0506_0708);
+        assert_eq!(encoded, b"key\x08\x07\x06\x05\x04\x03\x02\x01");
+
+        let (key, ts) = Key::from_rocksdb_with_ts(&encoded).unwrap();
+        assert!(key == k);
+        assert_eq!(ts, 0x0102_0304_0506_0708);
+
+        let (key, ts) = Key::from_rocksdb_with_ts(&[0xff; 8]).unwrap();
+        assert!(key.as_raw().is_empty());
+        assert_eq!(ts, u64::MAX);
+
+        assert!(Key::from_rocksdb_with_ts(b"").is_none());
+        assert!(Key::from_rocksdb_with_ts(b"1234567").is_none());
+    }
+
+    #[test]
+    fn eq_slice() {
+        let key = Key::from_raw(&[7; 100]);
+        let equal = [7; 100];
+        let shorter = [7; 99];
+        let mut different = [7; 100];
+        different[99] = 8;
+
+        let before = allocs();
+        assert!(key.eq_slice(&equal));
+        assert!(!key.eq_slice(&shorter));
+        assert!(!key.eq_slice(&different));
+        assert!(key == equal[..]);
+        assert!(key != different[..]);
+        assert_eq!(allocs(), before);
+
+        assert!(Key::from_raw(b"").eq_slice(b""));
+        // Building a key to compare with does allocate.
+        assert!(key == Key::from_raw(&equal));
+        assert!(allocs() > before);
     }
 }
@@ -12,9 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod block;
+mod hashed_key;
+mod index;
+mod join;
 mod key;
+mod range;
+mod sampler;
+mod trie;
 
+pub use block::*;
+pub use hashed_key::*;
+pub use index::*;
+pub use join::*;
 pub use key::*;
+pub use range::*;
+pub use sampler::*;
+pub use trie::*;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,333 @@
+use std::{error, fmt};
+
+use crate::Key;
+
+/// The keys from `start` up to, but excluding, `end`.
+///
+/// The empty `start` key is the start of the keyspace, and an `end` of `None`
+/// is unbounded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyRange {
+    pub start: Key,
+    pub end: Option<Key>,
+}
+
+impl KeyRange {
+    /// The keys starting with `prefix`.
+    pub fn from_prefix(prefix: &[u8]) -> KeyRange {
+        KeyRange {
+            start: Key::from_raw(prefix),
+            end: Key::prefix_upper_bound(prefix),
+        }
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        *key >= self.start && self.end.as_ref().map_or(true, |end| key < end)
+    }
+
+    /// Whether no key falls in the range.
+    pub fn is_empty(&self) -> bool {
+        self.end.as_ref().map_or(false, |end| *end <= self.start)
+    }
+}
+
+/// The first hole in a set of ranges expected to cover the keyspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoverageError {
+    /// No range covers the keys from `start` up to `end`, or to the end of
+    /// the keyspace if `end` is `None`.
+    Gap {
+        start: Vec<u8>,
+        end: Option<Vec<u8>>,
+    },
+    /// More than one range covers the keys from `start`.
+    Overlap { start: Vec<u8> },
+    /// A range ends before it starts.
+    Inverted { start: Vec<u8>, end: Vec<u8> },
+}
+
+impl fmt::Display for CoverageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoverageError::Gap {
+                start,
+                end: Some(end),
+            } => write!(f, "gap between {:?} and {:?}", start, end),
+            CoverageError::Gap { start, end: None } => write!(f, "gap after {:?}", start),
+            CoverageError::Overlap { start } => write!(f, "overlap from {:?}", start),
+            CoverageError::Inverted { start, end } => {
+                write!(f, "range from {:?} ends before it at {:?}", start, end)
+            }
+        }
+    }
+}
+
+impl error::Error for CoverageError {}
+
+/// Checks that `ranges`, in any order, cover the whole keyspace exactly once,
+/// e.g. to validate a partition map.
+pub fn check_coverage(ranges: &[KeyRange]) -> Result<(), CoverageError> {
+    for range in ranges {
+        if let Some(end) = range.end.as_ref().filter(|end| **end < range.start) {
+            return Err(CoverageError::Inverted {
+                start: range.start.as_raw().to_vec(),
+                end: end.as_raw().to_vec(),
+            });
+        }
+    }
+
+    let mut sorted: Vec<&KeyRange> = ranges.iter().collect();
+    sorted.sort_by(|a, b| a.start.cmp(&b.start));
+
+    // The first key not yet covered, `None` once the keyspace is covered.
+    let mut next = Some(Key::from_raw(b""));
+    for range in sorted {
+        let expected = match &next {
+            Some(expected) => expected,
+            None => {
+                return Err(CoverageError::Overlap {
+                    start: range.start.as_raw().to_vec(),
+                })
+            }
+        };
+        if range.start > *expected {
+            return Err(CoverageError::Gap {
+                start: expected.as_raw().to_vec(),
+                end: Some(range.start.as_raw().to_vec()),
+            });
+        }
+        if range.start < *expected {
+            return Err(CoverageError::Overlap {
+                start: range.start.as_raw().to_vec(),
+            });
+        }
+        next = range.end.clone();
+    }
+
+    match next {
+        Some(start) => Err(CoverageError::Gap {
+            start: start.into_raw(),
+            end: None,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// A deletion of the keys from `start` up to, but excluding, `end` at `ts`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RangeTombstone {
+    pub start: Key,
+    pub end: Key,
+    pub ts: u64,
+}
+
+/// Merges runs of deleted keys into range tombstones, as long as each key is
+/// the `next` of the one before it and they were deleted at the same ts.
+///
+/// `deleted` is expected in key order; a single deleted key becomes the range
+/// `[key, key.next())`.
+pub fn coalesce_tombstones(deleted: &[(Key, u64)]) -> Vec<RangeTombstone> {
+    let mut ranges: Vec<RangeTombstone> = Vec::new();
+    for (key, ts) in deleted {
+        if let Some(last) = ranges.last_mut() {
+            if last.ts == *ts && last.end == *key {
+                last.end = key.next();
+                continue;
+            }
+        }
+        ranges.push(RangeTombstone {
+            start: key.clone(),
+            end: key.next(),
+            ts: *ts,
+        });
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn range(start: &[u8], end: Option<&[u8]>) -> KeyRange {
+        KeyRange {
+            start: Key::from_raw(start),
+            end: end.map(Key::from_raw),
+        }
+    }
+
+    #[test]
+    fn from_prefix() {
+        let keys: Vec<&[u8]> = vec![
+            b"",
+            b"a",
+            b"a\x00",
+            b"a\xff",
+            b"a\xff\xff",
+            b"ab",
+            b"b",
+            b"\xff",
+            b"\xff\x00",
+        ];
+        for prefix in [&b""[..], b"a", b"a\xff", b"\xff"] {
+            let r = KeyRange::from_prefix(prefix);
+            assert!(!r.is_empty());
+            for key in &keys {
+                assert_eq!(
+                    r.contains(&Key::from_raw(key)),
+                    key.starts_with(prefix),
+                    "{:?} in {:?}",
+                    key,
+                    r
+                );
+            }
+        }
+        assert_eq!(KeyRange::from_prefix(b"a\xff"), range(b"a\xff", Some(b"b")));
+        assert_eq!(KeyRange::from_prefix(b"\xff"), range(b"\xff", None));
+    }
+
+    #[test]
+    fn range_contains() {
+        let r = range(b"b", Some(b"d"));
+        assert!(!r.contains(&Key::from_raw(b"a")));
+        assert!(r.contains(&Key::from_raw(b"b")));
+        assert!(r.contains(&Key::from_raw(b"c\xff")));
+        assert!(!r.contains(&Key::from_raw(b"d")));
+        assert!(range(b"", None).contains(&Key::from_raw(b"\xff\xff")));
+
+        assert!(!r.is_empty());
+        assert!(range(b"b", Some(b"b")).is_empty());
+        assert!(range(b"c", Some(b"b")).is_empty());
+        assert!(!range(b"\xff", None).is_empty());
+    }
+
+    #[test]
+    fn coverage_complete() {
+        let ranges = [
+            range(b"m", Some(b"t")),
+            range(b"", Some(b"c")),
+            range(b"t", None),
+            range(b"c", Some(b"m")),
+        ];
+        assert_eq!(check_coverage(&ranges), Ok(()));
+        assert_eq!(check_coverage(&[range(b"", None)]), Ok(()));
+    }
+
+    #[test]
+    fn coverage_gap() {
+        let ranges = [range(b"", Some(b"c")), range(b"d", None)];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Gap {
+                start: b"c".to_vec(),
+                end: Some(b"d".to_vec()),
+            })
+        );
+
+        let ranges = [range(b"a", None)];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Gap {
+                start: vec![],
+                end: Some(b"a".to_vec()),
+            })
+        );
+
+        let ranges = [range(b"", Some(b"z"))];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Gap {
+                start: b"z".to_vec(),
+                end: None,
+            })
+        );
+        assert!(check_coverage(&[]).is_err());
+    }
+
+    #[test]
+    fn coverage_inverted() {
+        let ranges = [
+            range(b"", Some(b"m")),
+            range(b"m", Some(b"k")),
+            range(b"k", None),
+        ];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Inverted {
+                start: b"m".to_vec(),
+                end: b"k".to_vec(),
+            })
+        );
+        // An empty range isn't inverted.
+        let ranges = [
+            range(b"", Some(b"m")),
+            range(b"m", Some(b"m")),
+            range(b"m", None),
+        ];
+        assert!(!matches!(
+            check_coverage(&ranges),
+            Err(CoverageError::Inverted { .. })
+        ));
+    }
+
+    #[test]
+    fn coverage_overlap() {
+        let ranges = [range(b"", Some(b"d")), range(b"c", None)];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Overlap {
+                start: b"c".to_vec()
+            })
+        );
+
+        let ranges = [range(b"", None), range(b"x", Some(b"y"))];
+        assert_eq!(
+            check_coverage(&ranges),
+            Err(CoverageError::Overlap {
+                start: b"x".to_vec()
+            })
+        );
+    }
+
+    #[test]
+    fn coalesce_adjacent_tombstones() {
+        let a = Key::from_raw(b"a");
+        let deleted = [(a.clone(), 5), (a.next(), 5), (a.next().next(), 5)];
+        assert_eq!(
+            coalesce_tombstones(&deleted),
+            vec![RangeTombstone {
+                start: a.clone(),
+                end: a.next().next().next(),
+                ts: 5,
+            }]
+        );
+        assert!(coalesce_tombstones(&[]).is_empty());
+    }
+
+    #[test]
+    fn coalesce_keeps_gaps_and_ts_apart() {
+        let a = Key::from_raw(b"a");
+        let b = Key::from_raw(b"b");
+        let deleted = [(a.clone(), 5), (a.next(), 6), (b.clone(), 6)];
+        assert_eq!(
+            coalesce_tombstones(&deleted),
+            vec![
+                RangeTombstone {
+                    start: a.clone(),
+                    end: a.next(),
+                    ts: 5,
+                },
+                RangeTombstone {
+                    start: a.next(),
+                    end: a.next().next(),
+                    ts: 6,
+                },
+                RangeTombstone {
+                    start: b.clone(),
+                    end: b.next(),
+                    ts: 6,
+                },
+            ]
+        );
+    }
+}
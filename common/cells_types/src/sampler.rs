@@ -0,0 +1,81 @@
+use crate::Key;
+
+/// Deterministically samples every `every`-th observed key, starting with the
+/// first one, e.g. to build histograms from a scan.
+pub struct KeySampler {
+    every: usize,
+    seen: usize,
+    samples: Vec<Key>,
+}
+
+impl KeySampler {
+    /// A sampler keeping one in every `every` keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is 0.
+    pub fn new(every: usize) -> Self {
+        assert!(every > 0, "sample interval must be positive");
+        KeySampler {
+            every,
+            seen: 0,
+            samples: vec![],
+        }
+    }
+
+    /// Observes the next key of the scan, returns it if it was sampled.
+    pub fn observe(&mut self, key: &Key) -> Option<&Key> {
+        let sampled = self.seen % self.every == 0;
+        self.seen += 1;
+        if !sampled {
+            return None;
+        }
+        self.samples.push(key.clone());
+        self.samples.last()
+    }
+
+    pub fn samples(&self) -> &[Key] {
+        &self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn sample_every_nth() {
+        let keys: Vec<Key> = (0u8..10).map(|i| Key::from_raw(&[i])).collect();
+
+        let mut sampler = KeySampler::new(3);
+        let sampled: Vec<bool> = keys.iter().map(|k| sampler.observe(k).is_some()).collect();
+        assert_eq!(
+            sampled,
+            [true, false, false, true, false, false, true, false, false, true]
+        );
+
+        let samples: Vec<&[u8]> = sampler.samples().iter().map(|k| k.as_raw()).collect();
+        assert_eq!(samples, [&[0], &[3], &[6], &[9]]);
+    }
+
+    #[test]
+    fn sample_first_key() {
+        let mut sampler = KeySampler::new(1);
+        let k = Key::from_raw(b"a");
+        assert!(sampler.observe(&k) == Some(&k));
+        assert!(sampler.observe(&k) == Some(&k));
+        assert_eq!(sampler.samples().len(), 2);
+
+        let mut sampler = KeySampler::new(100);
+        assert!(sampler.observe(&k).is_some());
+        assert!(sampler.observe(&k).is_none());
+        assert_eq!(sampler.samples().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "sample interval must be positive")]
+    fn sample_every_zero() {
+        KeySampler::new(0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use crate::Key;
+
+/// Maps key prefixes to values and answers longest-prefix-match lookups,
+/// e.g. for routing keys to shards.
+pub struct PrefixTrie<V> {
+    root: Node<V>,
+}
+
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<u8, Node<V>>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Node {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> PrefixTrie<V> {
+    pub fn new() -> Self {
+        PrefixTrie {
+            root: Node::default(),
+        }
+    }
+
+    /// Stores `value` for `prefix`, returning the value previously stored for
+    /// the same prefix.
+    pub fn insert(&mut self, prefix: &[u8], value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for b in prefix {
+            node = node.children.entry(*b).or_default();
+        }
+        node.value.replace(value)
+    }
+
+    /// Returns the value of the longest stored prefix of `key`.
+    pub fn longest_match(&self, key: &Key) -> Option<&V> {
+        let mut node = &self.root;
+        let mut found = node.value.as_ref();
+        for b in key.as_raw() {
+            match node.children.get(b) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if node.value.is_some() {
+                found = node.value.as_ref();
+            }
+        }
+        found
+    }
+}
+
+impl<V> Default for PrefixTrie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn longest_match() {
+        let mut trie = PrefixTrie::new();
+        assert_eq!(trie.insert(b"t1", 1), None);
+        assert_eq!(trie.insert(b"t1_a", 2), None);
+        assert_eq!(trie.insert(b"t1_abc", 3), None);
+        assert_eq!(trie.insert(b"t2", 4), None);
+
+        let lookup = |k: &[u8]| trie.longest_match(&Key::from_raw(k)).copied();
+        assert_eq!(lookup(b"t1"), Some(1));
+        assert_eq!(lookup(b"t1_"), Some(1));
+        assert_eq!(lookup(b"t1_ab"), Some(2));
+        assert_eq!(lookup(b"t1_abc"), Some(3));
+        assert_eq!(lookup(b"t1_abcd"), Some(3));
+        assert_eq!(lookup(b"t2_abc"), Some(4));
+        assert_eq!(lookup(b"t3"), None);
+        assert_eq!(lookup(b"t"), None);
+        assert_eq!(lookup(b""), None);
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut trie = PrefixTrie::new();
+        assert_eq!(trie.insert(b"", "default"), None);
+        assert_eq!(trie.insert(b"a", "a"), None);
+        assert_eq!(trie.insert(b"a", "b"), Some("a"));
+
+        assert_eq!(trie.longest_match(&Key::from_raw(b"ab")), Some(&"b"));
+        assert_eq!(trie.longest_match(&Key::from_raw(b"b")), Some(&"default"));
+    }
+}
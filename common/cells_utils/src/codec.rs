@@ -1 +1,8 @@
+pub mod aggregate;
+pub mod bitset;
+pub mod bytes;
+pub mod geo;
 pub mod number;
+pub mod sparse;
+pub mod string_table;
+pub mod wal;
@@ -0,0 +1,121 @@
+use super::number::{self, NumberEncoder, Result};
+
+/// Summary stats of a downsampled series of points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+/// Size in bytes of an encoded `Aggregate`.
+pub const AGGREGATE_SIZE: usize = 32;
+
+impl Aggregate {
+    /// Combines the stats of two series, returns `None` if the total count
+    /// overflows.
+    pub fn merge(&self, other: &Aggregate) -> Option<Aggregate> {
+        Some(Aggregate {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            sum: self.sum + other.sum,
+            count: self.count.checked_add(other.count)?,
+        })
+    }
+}
+
+/// Writes `agg` as `min || max || sum || count`, the floats with the
+/// comparable f64 encoding and the count as a big-endian u64.
+pub fn encode_aggregate(buf: &mut Vec<u8>, agg: &Aggregate) -> Result<()> {
+    buf.encode_f64(agg.min)?;
+    buf.encode_f64(agg.max)?;
+    buf.encode_f64(agg.sum)?;
+    buf.encode_u64(agg.count)
+}
+
+/// Decodes an aggregate written by `encode_aggregate`, advancing `data` past
+/// it.
+pub fn decode_aggregate(data: &mut &[u8]) -> Result<Aggregate> {
+    let mut rest = *data;
+    let agg = Aggregate {
+        min: number::decode_f64(&mut rest)?,
+        max: number::decode_f64(&mut rest)?,
+        sum: number::decode_f64(&mut rest)?,
+        count: number::decode_u64(&mut rest)?,
+    };
+    *data = rest;
+    Ok(agg)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn aggregate_serialize() {
+        let cases = [
+            Aggregate {
+                min: 1.5,
+                max: 9.25,
+                sum: 42.0,
+                count: 7,
+            },
+            Aggregate {
+                min: f64::MIN,
+                max: f64::MAX,
+                sum: -0.0,
+                count: u64::MAX,
+            },
+            Aggregate {
+                min: f64::NEG_INFINITY,
+                max: f64::INFINITY,
+                sum: f64::MIN_POSITIVE,
+                count: 0,
+            },
+        ];
+        for agg in cases {
+            let mut buf = vec![];
+            encode_aggregate(&mut buf, &agg).unwrap();
+            assert_eq!(buf.len(), AGGREGATE_SIZE);
+            buf.push(0xff);
+
+            let mut data = buf.as_slice();
+            assert_eq!(decode_aggregate(&mut data).unwrap(), agg);
+            assert_eq!(data, &[0xff]);
+
+            let mut truncated = &buf[..AGGREGATE_SIZE - 1];
+            assert!(decode_aggregate(&mut truncated).is_err());
+            assert_eq!(truncated.len(), AGGREGATE_SIZE - 1);
+        }
+    }
+
+    #[test]
+    fn aggregate_merge() {
+        let a = Aggregate {
+            min: -1.0,
+            max: 3.0,
+            sum: 2.0,
+            count: 2,
+        };
+        let b = Aggregate {
+            min: 0.5,
+            max: 8.0,
+            sum: 8.5,
+            count: u64::MAX - 2,
+        };
+        assert_eq!(
+            a.merge(&b),
+            Some(Aggregate {
+                min: -1.0,
+                max: 8.0,
+                sum: 10.5,
+                count: u64::MAX,
+            })
+        );
+
+        let c = Aggregate { count: 1, ..a };
+        assert_eq!(a.merge(&b).unwrap().merge(&c), None);
+    }
+}
@@ -0,0 +1,66 @@
+use super::number::{self, Error, NumberEncoder, Result};
+
+/// Writes `bits` as a varint count followed by the bits packed 8 per byte,
+/// least significant bit first.
+pub fn encode_bitset(buf: &mut Vec<u8>, bits: &[bool]) -> Result<()> {
+    buf.encode_var_u64(bits.len() as u64)?;
+    for chunk in bits.chunks(8) {
+        let byte = chunk
+            .iter()
+            .enumerate()
+            .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i));
+        buf.push(byte);
+    }
+    Ok(())
+}
+
+/// Decodes a bitset written by `encode_bitset`, advancing `data` past it.
+pub fn decode_bitset(data: &mut &[u8]) -> Result<Vec<bool>> {
+    let count = number::decode_var_u64(data)?;
+    let byte_len = count / 8 + (count % 8 != 0) as u64;
+    if (data.len() as u64) < byte_len {
+        return Err(Error::EncoderUnexpectedEOF);
+    }
+
+    let (packed, rest) = data.split_at(byte_len as usize);
+    let bits = (0..count as usize)
+        .map(|i| packed[i / 8] & (1 << (i % 8)) != 0)
+        .collect();
+    *data = rest;
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn bitset_serialize() {
+        for count in [0, 1, 7, 8, 9, 16, 100] {
+            let bits: Vec<bool> = (0..count).map(|i| i % 3 == 0).collect();
+
+            let mut buf = vec![];
+            encode_bitset(&mut buf, &bits).unwrap();
+            assert_eq!(buf.len(), 1 + (count + 7) / 8);
+
+            let mut data = buf.as_slice();
+            assert_eq!(decode_bitset(&mut data).unwrap(), bits);
+            assert!(data.is_empty());
+        }
+    }
+
+    #[test]
+    fn bitset_layout() {
+        let mut buf = vec![];
+        encode_bitset(
+            &mut buf,
+            &[true, false, true, true, false, false, false, false, true],
+        )
+        .unwrap();
+        assert_eq!(buf, &[9, 0b0000_1101, 0b0000_0001]);
+
+        assert!(decode_bitset(&mut &buf[..2]).is_err());
+        assert!(decode_bitset(&mut &[0xff, 0xff, 0xff, 0xff, 0x0f][..]).is_err());
+    }
+}
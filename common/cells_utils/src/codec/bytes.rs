@@ -0,0 +1,223 @@
+use super::number::{self, Error, NumberEncoder, Result};
+
+/// Number of data bytes per group in the memcomparable encoding.
+pub const ENC_GROUP_SIZE: usize = 8;
+const ENC_MARKER: u8 = 0xff;
+const ENC_PAD: u8 = 0;
+
+/// Returns the length of `len` bytes once memcomparable encoded.
+pub fn encoded_bytes_len(len: usize) -> usize {
+    (len / ENC_GROUP_SIZE + 1) * (ENC_GROUP_SIZE + 1)
+}
+
+/// Writes `data` in groups of 8 bytes, each followed by a marker byte. The
+/// last group is padded with `0x00` and its marker is `0xff` minus the pad
+/// count, so the encoding compares the same way as the raw bytes.
+pub fn encode_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.reserve(encoded_bytes_len(data.len()));
+    for group in data.chunks(ENC_GROUP_SIZE) {
+        if group.len() < ENC_GROUP_SIZE {
+            break;
+        }
+        buf.extend_from_slice(group);
+        buf.push(ENC_MARKER);
+    }
+
+    let tail = &data[data.len() / ENC_GROUP_SIZE * ENC_GROUP_SIZE..];
+    let pad = ENC_GROUP_SIZE - tail.len();
+    buf.extend_from_slice(tail);
+    buf.resize(buf.len() + pad, ENC_PAD);
+    buf.push(ENC_MARKER - pad as u8);
+}
+
+/// Decodes bytes written by `encode_bytes`, advancing `data` past them.
+pub fn decode_bytes(data: &mut &[u8]) -> Result<Vec<u8>> {
+    let mut key = Vec::with_capacity(data.len() / (ENC_GROUP_SIZE + 1) * ENC_GROUP_SIZE);
+    let mut offset = 0;
+    loop {
+        let group = data
+            .get(offset..offset + ENC_GROUP_SIZE + 1)
+            .ok_or(Error::EncoderUnexpectedEOF)?;
+        offset += ENC_GROUP_SIZE + 1;
+
+        let (bytes, marker) = group.split_at(ENC_GROUP_SIZE);
+        let pad = (ENC_MARKER - marker[0]) as usize;
+        if pad == 0 {
+            key.extend_from_slice(bytes);
+            continue;
+        }
+        if pad > ENC_GROUP_SIZE {
+            return Err(Error::InvalidPadding);
+        }
+
+        let (tail, padding) = bytes.split_at(ENC_GROUP_SIZE - pad);
+        if padding.iter().any(|&b| b != ENC_PAD) {
+            return Err(Error::InvalidPadding);
+        }
+        key.extend_from_slice(tail);
+        *data = &data[offset..];
+        return Ok(key);
+    }
+}
+
+/// Writes `data` as a var_i64 length followed by the raw bytes. Cheaper than
+/// `encode_bytes`, but the encoding doesn't sort like the input, so it is
+/// only meant for values.
+pub fn encode_compact_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.encode_var_i64(data.len() as i64).unwrap();
+    buf.extend_from_slice(data);
+}
+
+/// Decodes bytes written by `encode_compact_bytes`, advancing `data` past
+/// them.
+pub fn decode_compact_bytes(data: &mut &[u8]) -> Result<Vec<u8>> {
+    let mut rest = *data;
+    let len = number::decode_var_i64(&mut rest)?;
+    let len = usize::try_from(len).map_err(|_| Error::EncoderValueOverflow)?;
+    if rest.len() < len {
+        return Err(Error::EncoderUnexpectedEOF);
+    }
+    let (bytes, rest) = rest.split_at(len);
+    *data = rest;
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn bytes_serialize() {
+        let cases: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0],
+            vec![1, 2, 3],
+            vec![0; 8],
+            vec![0xff; 8],
+            (0..9).collect(),
+            (0..17).collect(),
+        ];
+        for raw in cases {
+            let mut buf = vec![];
+            encode_bytes(&mut buf, &raw);
+            assert_eq!(buf.len(), encoded_bytes_len(raw.len()));
+            buf.push(0xaa);
+
+            let mut data = buf.as_slice();
+            assert_eq!(decode_bytes(&mut data).unwrap(), raw);
+            assert_eq!(data, &[0xaa]);
+        }
+    }
+
+    #[test]
+    fn bytes_order() {
+        // Two-segment keys, in the order their segments sort.
+        let keys: Vec<[&[u8]; 2]> = vec![
+            [b"", b""],
+            [b"", b"\xff"],
+            [b"\x00", b""],
+            [b"\x00", b"\x00"],
+            [b"\x00\x00", b""],
+            [b"a", b"\xff\xff"],
+            [b"a\x00", b""],
+            [b"a\x00b", b"c"],
+            [b"a\xff\xff\xff\xff\xff\xff\xff", b""],
+            [b"a\xff\xff\xff\xff\xff\xff\xff\x00", b""],
+            [b"b", b""],
+        ];
+        let encoded: Vec<Vec<u8>> = keys
+            .iter()
+            .map(|segments| {
+                let mut buf = vec![];
+                for segment in segments {
+                    encode_bytes(&mut buf, segment);
+                }
+                buf
+            })
+            .collect();
+        for (pair, keys) in encoded.windows(2).zip(keys.windows(2)) {
+            assert!(pair[0] < pair[1], "{:?} >= {:?}", keys[0], keys[1]);
+        }
+
+        for (buf, segments) in encoded.iter().zip(&keys) {
+            let mut data = buf.as_slice();
+            for segment in segments {
+                assert_eq!(decode_bytes(&mut data).unwrap(), *segment);
+            }
+            assert!(data.is_empty());
+        }
+    }
+
+    #[test]
+    fn bytes_layout() {
+        let mut buf = vec![];
+        encode_bytes(&mut buf, &[1, 2, 3]);
+        assert_eq!(buf, [1, 2, 3, 0, 0, 0, 0, 0, 0xfa]);
+
+        buf.clear();
+        encode_bytes(&mut buf, &[]);
+        assert_eq!(buf, [0, 0, 0, 0, 0, 0, 0, 0, 0xf7]);
+
+        buf.clear();
+        encode_bytes(&mut buf, &[9; 8]);
+        assert_eq!(
+            buf,
+            [9, 9, 9, 9, 9, 9, 9, 9, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0xf7]
+        );
+    }
+
+    #[test]
+    fn bytes_malformed() {
+        let mut buf = vec![];
+        encode_bytes(&mut buf, &[1, 2, 3]);
+        assert!(matches!(
+            decode_bytes(&mut &buf[..8]),
+            Err(Error::EncoderUnexpectedEOF)
+        ));
+
+        let mut bad_marker = buf.clone();
+        bad_marker[8] = 0;
+        assert!(matches!(
+            decode_bytes(&mut bad_marker.as_slice()),
+            Err(Error::InvalidPadding)
+        ));
+
+        let mut bad_pad = buf;
+        bad_pad[7] = 1;
+        assert!(matches!(
+            decode_bytes(&mut bad_pad.as_slice()),
+            Err(Error::InvalidPadding)
+        ));
+    }
+
+    #[test]
+    fn compact_bytes_serialize() {
+        for raw in [vec![], vec![0], (0..=255).collect(), vec![0xab; 100_000]] {
+            let mut buf = vec![];
+            encode_compact_bytes(&mut buf, &raw);
+            buf.extend_from_slice(&[1, 2]);
+
+            let mut data = buf.as_slice();
+            assert_eq!(decode_compact_bytes(&mut data).unwrap(), raw);
+            assert_eq!(data, &[1, 2]);
+        }
+
+        let mut buf = vec![];
+        encode_compact_bytes(&mut buf, b"");
+        assert_eq!(buf, [0]);
+
+        encode_compact_bytes(&mut buf, b"abc");
+        let mut truncated = &buf[1..buf.len() - 1];
+        assert!(decode_compact_bytes(&mut truncated).is_err());
+        assert_eq!(truncated.len(), 3);
+
+        // A negative length.
+        let mut negative = vec![];
+        negative.encode_var_i64(-1).unwrap();
+        assert!(matches!(
+            decode_compact_bytes(&mut negative.as_slice()),
+            Err(Error::EncoderValueOverflow)
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+/// Longest geohash in bytes, at which each axis is quantized to 32 bits.
+pub const MAX_GEOHASH_PRECISION: u8 = 8;
+
+const LAT_RANGE: (f64, f64) = (-90.0, 90.0);
+const LON_RANGE: (f64, f64) = (-180.0, 180.0);
+
+fn quantize(v: f64, (min, max): (f64, f64)) -> u32 {
+    // `as` saturates, clamping out of range coordinates to the edges.
+    ((v - min) / (max - min) * (1u64 << 32) as f64) as u32
+}
+
+fn interleave(x: u32, y: u32) -> u64 {
+    (0..32).fold(0, |code, i| {
+        code | ((x as u64 >> i) & 1) << (2 * i + 1) | ((y as u64 >> i) & 1) << (2 * i)
+    })
+}
+
+fn deinterleave(code: u64) -> (u32, u32) {
+    (0..32).fold((0, 0), |(x, y), i| {
+        (
+            x | (((code >> (2 * i + 1)) & 1) as u32) << i,
+            y | (((code >> (2 * i)) & 1) as u32) << i,
+        )
+    })
+}
+
+/// The center of the `q`th of `2^bits` cells spanning the range.
+fn cell_center(q: u32, bits: usize, (min, max): (f64, f64)) -> f64 {
+    let q = if bits == 0 { 0 } else { q >> (32 - bits) };
+    min + (q as f64 + 0.5) * (max - min) / (1u64 << bits) as f64
+}
+
+/// Encodes a coordinate as a Z-order key of `precision` bytes, capped at
+/// `MAX_GEOHASH_PRECISION`, interleaving longitude and latitude bits from
+/// the most significant, so nearby points share a key prefix.
+///
+/// The bytes are meant to be used as the raw bytes of a key.
+pub fn encode_geohash(lat: f64, lon: f64, precision: u8) -> Vec<u8> {
+    let precision = precision.min(MAX_GEOHASH_PRECISION) as usize;
+    let code = interleave(quantize(lon, LON_RANGE), quantize(lat, LAT_RANGE));
+    code.to_be_bytes()[..precision].to_vec()
+}
+
+/// Decodes a key written by `encode_geohash` into the `(lat, lon)` center of
+/// the cell it covers.
+pub fn decode_geohash(key: &[u8]) -> (f64, f64) {
+    let len = key.len().min(MAX_GEOHASH_PRECISION as usize);
+    let mut bytes = [0; MAX_GEOHASH_PRECISION as usize];
+    bytes[..len].copy_from_slice(&key[..len]);
+    let (x, y) = deinterleave(u64::from_be_bytes(bytes));
+
+    // Longitude takes the first of every pair of bits.
+    let bits = len * 8;
+    let lat = cell_center(y, bits / 2, LAT_RANGE);
+    let lon = cell_center(x, (bits + 1) / 2, LON_RANGE);
+    (lat, lon)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn shared_prefix(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b).take_while(|(x, y)| x == y).count()
+    }
+
+    #[test]
+    fn geohash_roundtrip() {
+        let points = [
+            (0.0, 0.0),
+            (48.8584, 2.2945),
+            (-33.8568, 151.2153),
+            (90.0, 180.0),
+            (-90.0, -180.0),
+        ];
+        for (lat, lon) in points {
+            let key = encode_geohash(lat, lon, MAX_GEOHASH_PRECISION);
+            assert_eq!(key.len(), 8);
+            let (lat1, lon1) = decode_geohash(&key);
+            assert!((lat1 - lat).abs() < 1e-6, "{} {}", lat, lat1);
+            assert!((lon1 - lon).abs() < 1e-6, "{} {}", lon, lon1);
+
+            // A shorter hash decodes to the center of a larger cell.
+            let (lat2, lon2) = decode_geohash(&key[..2]);
+            assert!((lat2 - lat).abs() <= 180.0 / 256.0);
+            assert!((lon2 - lon).abs() <= 360.0 / 256.0);
+        }
+
+        assert_eq!(encode_geohash(1.0, 1.0, 3).len(), 3);
+        assert_eq!(encode_geohash(1.0, 1.0, 100).len(), 8);
+        assert_eq!(decode_geohash(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn geohash_nearby_share_prefix() {
+        let eiffel = encode_geohash(48.8584, 2.2945, 8);
+        let louvre = encode_geohash(48.8606, 2.3376, 8);
+        let sydney = encode_geohash(-33.8568, 151.2153, 8);
+        assert!(shared_prefix(&eiffel, &louvre) >= 2);
+        assert!(shared_prefix(&eiffel, &louvre) > shared_prefix(&eiffel, &sydney));
+    }
+}
@@ -5,8 +5,10 @@ use std::{io::Write, mem};
 
 const SIGN_MARK: u64 = 0x8000000000000000;
 pub const U64_SIZE: usize = 8;
+pub const U32_SIZE: usize = 4;
 pub const I64_SIZE: usize = 8;
 pub const F64_SIZE: usize = 8;
+pub const MAX_VAR_U64_LEN: usize = 10;
 
 fn order_encode_i64(v: i64) -> u64 {
     v as u64 ^ SIGN_MARK
@@ -25,6 +27,24 @@ fn order_encode_f64(v: f64) -> u64 {
     }
 }
 
+fn order_encode_f32(v: f32) -> u32 {
+    let u = v.to_bits();
+    if v.is_sign_positive() {
+        u | (1 << 31)
+    } else {
+        !u
+    }
+}
+
+fn order_decode_f32(u: u32) -> f32 {
+    let u = if u & (1 << 31) > 0 {
+        u & !(1 << 31)
+    } else {
+        !u
+    };
+    f32::from_bits(u)
+}
+
 fn order_decode_f64(u: u64) -> f64 {
     let u = if u & SIGN_MARK > 0 {
         u & (!SIGN_MARK)
@@ -41,6 +61,28 @@ pub enum Error {
 
     #[snafu(display("Encoder Error: Unexpected eof"))]
     EncoderUnexpectedEOF,
+
+    #[snafu(display("Encoder Error: Value overflow"))]
+    EncoderValueOverflow,
+
+    #[snafu(display("Encoder Error: Non-canonical varint"))]
+    NonCanonicalVarint,
+
+    #[snafu(display("Encoder Error: Invalid padding"))]
+    InvalidPadding,
+
+    #[snafu(display(
+        "Encoder Error: Checksum mismatch, expected 0x{:X} got 0x{:X}",
+        expected,
+        actual
+    ))]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    #[snafu(display("Encoder Error: Duplicate table entry {}", index))]
+    DuplicateEntry { index: usize },
+
+    #[snafu(display("Encoder Error: Truncated record at offset {}", offset))]
+    TruncatedRecord { offset: usize },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -56,6 +98,13 @@ pub trait NumberEncoder: Write {
             .context(EncoderNumberFailSnafu)
     }
 
+    /// Writes `v` little-endian, for interop with data not written by this
+    /// codec.
+    fn encode_u64_le(&mut self, v: u64) -> Result<()> {
+        self.write_u64::<LittleEndian>(v)
+            .context(EncoderNumberFailSnafu)
+    }
+
     fn encode_f64(&mut self, f: f64) -> Result<()> {
         let u = order_encode_f64(f);
         self.encode_u64(u)
@@ -66,12 +115,29 @@ pub trait NumberEncoder: Write {
             .context(EncoderNumberFailSnafu)
     }
 
+    /// Writes `v` big-endian with the sign bit flipped, like `encode_i64`,
+    /// so encoded values sort in numeric order.
     fn encode_i32(&mut self, v: i32) -> Result<()> {
+        self.encode_u32(v as u32 ^ (1 << 31))
+    }
+
+    /// Writes `v` as plain little-endian two's complement, for interop with
+    /// data not written by this codec. Unlike `encode_u64_le`, this isn't
+    /// the byte-reverse of `encode_i32`, which flips the sign bit.
+    fn encode_i32_raw_le(&mut self, v: i32) -> Result<()> {
         self.write_i32::<LittleEndian>(v)
             .context(EncoderNumberFailSnafu)
     }
 
+    /// Writes `v` big-endian and order-preserving, like `encode_f64`.
     fn encode_f32(&mut self, v: f32) -> Result<()> {
+        self.encode_u32(order_encode_f32(v))
+    }
+
+    /// Writes `v` as plain little-endian IEEE 754, for interop with
+    /// data not written by this codec. Unlike `encode_u64_le`, this isn't
+    /// the byte-reverse of `encode_f32`, which flips the sign bit.
+    fn encode_f32_raw_le(&mut self, v: f32) -> Result<()> {
         self.write_f32::<LittleEndian>(v)
             .context(EncoderNumberFailSnafu)
     }
@@ -81,10 +147,38 @@ pub trait NumberEncoder: Write {
             .context(EncoderNumberFailSnafu)
     }
 
+    /// Writes `v` big-endian with the sign bit flipped, like `encode_i32`.
     fn encode_i16(&mut self, v: i16) -> Result<()> {
+        self.encode_u16(v as u16 ^ (1 << 15))
+    }
+
+    /// Writes `v` as plain little-endian two's complement, for interop with
+    /// data not written by this codec. Unlike `encode_u64_le`, this isn't
+    /// the byte-reverse of `encode_i16`, which flips the sign bit.
+    fn encode_i16_raw_le(&mut self, v: i16) -> Result<()> {
         self.write_i16::<LittleEndian>(v)
             .context(EncoderNumberFailSnafu)
     }
+
+    /// Writes `v` as unsigned LEB128, 7 bits per byte with the high bit as
+    /// continuation.
+    fn encode_var_u64(&mut self, mut v: u64) -> Result<()> {
+        let mut buf = [0; MAX_VAR_U64_LEN];
+        let mut i = 0;
+        while v >= 0x80 {
+            buf[i] = v as u8 | 0x80;
+            v >>= 7;
+            i += 1;
+        }
+        buf[i] = v as u8;
+        self.write_all(&buf[..=i]).context(EncoderNumberFailSnafu)
+    }
+
+    /// Writes `v` zigzag-encoded as a var_u64, so small negative values stay
+    /// as short as small positive ones.
+    fn encode_var_i64(&mut self, v: i64) -> Result<()> {
+        self.encode_var_u64(((v << 1) ^ (v >> 63)) as u64)
+    }
 }
 
 impl<T: Write> NumberEncoder for T {}
@@ -112,6 +206,12 @@ pub fn decode_u64(data: &mut &[u8]) -> Result<u64> {
     read_num_bytes(mem::size_of::<u64>(), data, BigEndian::read_u64)
 }
 
+/// Decodes a little-endian u64 written by `encode_u64_le`.
+#[inline]
+pub fn decode_u64_le(data: &mut &[u8]) -> Result<u64> {
+    read_num_bytes(mem::size_of::<u64>(), data, LittleEndian::read_u64)
+}
+
 #[inline]
 pub fn decode_f64(data: &mut &[u8]) -> Result<f64> {
     decode_u64(data).map(order_decode_f64)
@@ -124,11 +224,23 @@ pub fn decode_u32(data: &mut &[u8]) -> Result<u32> {
 
 #[inline]
 pub fn decode_i32(data: &mut &[u8]) -> Result<i32> {
+    decode_u32(data).map(|u| (u ^ (1 << 31)) as i32)
+}
+
+/// Decodes a little-endian i32 written by `encode_i32_raw_le`.
+#[inline]
+pub fn decode_i32_raw_le(data: &mut &[u8]) -> Result<i32> {
     read_num_bytes(mem::size_of::<i32>(), data, LittleEndian::read_i32)
 }
 
 #[inline]
 pub fn decode_f32(data: &mut &[u8]) -> Result<f32> {
+    decode_u32(data).map(order_decode_f32)
+}
+
+/// Decodes a little-endian f32 written by `encode_f32_raw_le`.
+#[inline]
+pub fn decode_f32_raw_le(data: &mut &[u8]) -> Result<f32> {
     read_num_bytes(mem::size_of::<f32>(), data, LittleEndian::read_f32)
 }
 
@@ -139,9 +251,86 @@ pub fn decode_u16(data: &mut &[u8]) -> Result<u16> {
 
 #[inline]
 pub fn decode_i16(data: &mut &[u8]) -> Result<i16> {
+    decode_u16(data).map(|u| (u ^ (1 << 15)) as i16)
+}
+
+/// Decodes a little-endian i16 written by `encode_i16_raw_le`.
+#[inline]
+pub fn decode_i16_raw_le(data: &mut &[u8]) -> Result<i16> {
     read_num_bytes(mem::size_of::<i16>(), data, LittleEndian::read_i16)
 }
 
+// The `peek_*` functions decode the value at the front of `data` like the
+// matching `decode_*`, but leave `data` where it is.
+
+#[inline]
+pub fn peek_i64(data: &&[u8]) -> Result<i64> {
+    decode_i64(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_u64(data: &&[u8]) -> Result<u64> {
+    decode_u64(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_f64(data: &&[u8]) -> Result<f64> {
+    decode_f64(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_u32(data: &&[u8]) -> Result<u32> {
+    decode_u32(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_i32(data: &&[u8]) -> Result<i32> {
+    decode_i32(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_f32(data: &&[u8]) -> Result<f32> {
+    decode_f32(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_u16(data: &&[u8]) -> Result<u16> {
+    decode_u16(&mut &data[..])
+}
+
+#[inline]
+pub fn peek_i16(data: &&[u8]) -> Result<i16> {
+    decode_i16(&mut &data[..])
+}
+
+/// Decodes an unsigned LEB128 value, advancing `data` past the consumed bytes.
+///
+/// Overlong encodings, with trailing zero groups, are rejected so every value
+/// has exactly one encoding. Every varint in the codec is read through here.
+pub fn decode_var_u64(data: &mut &[u8]) -> Result<u64> {
+    let mut v = 0;
+    for (i, &b) in data.iter().enumerate().take(MAX_VAR_U64_LEN) {
+        // The last byte only has room for the top bit of a u64.
+        if i == MAX_VAR_U64_LEN - 1 && b > 1 {
+            return Err(Error::EncoderValueOverflow);
+        }
+        v |= ((b & 0x7f) as u64) << (i * 7);
+        if b & 0x80 == 0 {
+            if i > 0 && b == 0 {
+                return Err(Error::NonCanonicalVarint);
+            }
+            *data = &data[i + 1..];
+            return Ok(v);
+        }
+    }
+    Err(Error::EncoderUnexpectedEOF)
+}
+
+#[inline]
+pub fn decode_var_i64(data: &mut &[u8]) -> Result<i64> {
+    decode_var_u64(data).map(|v| (v >> 1) as i64 ^ -((v & 1) as i64))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -251,6 +440,7 @@ mod tests {
         -1024,
     ];
 
+    #[allow(clippy::approx_constant)]
     const F32_TESTS: &[f32] = &[
         -1.0,
         0.0,
@@ -330,6 +520,7 @@ mod tests {
     }
 
     test_serialize!(u64_serialize, encode_u64, decode_u64, U64_TESTS);
+    test_serialize!(u64_le_serialize, encode_u64_le, decode_u64_le, U64_TESTS);
     test_serialize!(i64_serialize, encode_i64, decode_i64, I64_TESTS);
     test_serialize!(f64_serialize, encode_f64, decode_f64, F64_TESTS);
     test_serialize!(u32_serialize, encode_u32, decode_u32, U32_TESTS);
@@ -337,4 +528,185 @@ mod tests {
     test_serialize!(f32_serialize, encode_f32, decode_f32, F32_TESTS);
     test_serialize!(u16_serialize, encode_u16, decode_u16, U16_TESTS);
     test_serialize!(i16_serialize, encode_i16, decode_i16, I16_TESTS);
+    test_serialize!(
+        i32_raw_le_serialize,
+        encode_i32_raw_le,
+        decode_i32_raw_le,
+        I32_TESTS
+    );
+    test_serialize!(
+        f32_raw_le_serialize,
+        encode_f32_raw_le,
+        decode_f32_raw_le,
+        F32_TESTS
+    );
+    test_serialize!(
+        i16_raw_le_serialize,
+        encode_i16_raw_le,
+        decode_i16_raw_le,
+        I16_TESTS
+    );
+
+    #[test]
+    fn peek_does_not_advance() {
+        let mut buf = vec![];
+        buf.encode_u64(0x0102_0304_0506_0708).unwrap();
+        buf.encode_i32(-5).unwrap();
+        let mut data = buf.as_slice();
+
+        assert_eq!(peek_u64(&data).unwrap(), 0x0102_0304_0506_0708);
+        assert_eq!(peek_u32(&data).unwrap(), 0x0102_0304);
+        assert_eq!(peek_u16(&data).unwrap(), 0x0102);
+        assert_eq!(data.len(), 12);
+        assert_eq!(decode_u64(&mut data).unwrap(), 0x0102_0304_0506_0708);
+
+        assert_eq!(peek_i32(&data).unwrap(), -5);
+        assert_eq!(data.len(), 4);
+        assert_eq!(decode_i32(&mut data).unwrap(), -5);
+        assert!(peek_u64(&data).is_err());
+
+        let mut buf = vec![];
+        buf.encode_i64(-7).unwrap();
+        buf.encode_f64(1.5).unwrap();
+        buf.encode_f32(2.5).unwrap();
+        buf.encode_i16(-3).unwrap();
+        let mut data = buf.as_slice();
+        assert_eq!(peek_i64(&data).unwrap(), decode_i64(&mut data).unwrap());
+        assert_eq!(peek_f64(&data).unwrap(), decode_f64(&mut data).unwrap());
+        assert_eq!(peek_f32(&data).unwrap(), decode_f32(&mut data).unwrap());
+        assert_eq!(peek_i16(&data).unwrap(), decode_i16(&mut data).unwrap());
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn fixed_width_big_endian() {
+        // Big-endian with the sign bit flipped, like the 64-bit encoders.
+        let mut buf = vec![];
+        buf.encode_i32(1).unwrap();
+        assert_eq!(buf, [0x80, 0, 0, 1]);
+        buf.clear();
+        buf.encode_i16(1).unwrap();
+        assert_eq!(buf, [0x80, 1]);
+        buf.clear();
+        buf.encode_f32(1.0).unwrap();
+        assert_eq!(buf, (1.0f32.to_bits() | 1 << 31).to_be_bytes());
+
+        fn encoded<T: Copy>(vs: &[T], f: fn(&mut Vec<u8>, T) -> Result<()>) -> Vec<Vec<u8>> {
+            vs.iter()
+                .map(|&v| {
+                    let mut buf = vec![];
+                    f(&mut buf, v).unwrap();
+                    buf
+                })
+                .collect()
+        }
+        let is_sorted = |bufs: Vec<Vec<u8>>| bufs.windows(2).all(|w| w[0] < w[1]);
+        assert!(is_sorted(encoded(
+            &[i32::MIN, -256, -1, 0, 1, 256, i32::MAX],
+            |b, v| b.encode_i32(v)
+        )));
+        assert!(is_sorted(encoded(
+            &[i16::MIN, -1, 0, 1, i16::MAX],
+            |b, v| b.encode_i16(v)
+        )));
+        assert!(is_sorted(encoded(
+            &[f32::NEG_INFINITY, -1.5, -0.0, 0.0, 1.5, f32::INFINITY],
+            |b, v| b.encode_f32(v)
+        )));
+
+        // The raw little-endian forms keep the plain two's complement bytes.
+        buf.clear();
+        buf.encode_i32_raw_le(1).unwrap();
+        assert_eq!(buf, [1, 0, 0, 0]);
+        buf.clear();
+        buf.encode_i16_raw_le(-1).unwrap();
+        assert_eq!(buf, [0xff, 0xff]);
+    }
+
+    #[test]
+    fn u64_le_differs_from_be() {
+        let v = 0x0102_0304_0506_0708u64;
+        let mut buf = vec![];
+        buf.encode_u64_le(v).unwrap();
+        assert_eq!(buf, [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(decode_u64(&mut buf.as_slice()).unwrap(), v.swap_bytes());
+        assert!(decode_u64_le(&mut &buf[..7]).is_err());
+    }
+
+    #[test]
+    fn var_u64_serialize() {
+        for &v in U64_TESTS {
+            let mut buf = vec![];
+            buf.encode_var_u64(v).unwrap();
+            let mut data = buf.as_slice();
+            assert_eq!(v, decode_var_u64(&mut data).unwrap());
+            assert!(data.is_empty());
+        }
+
+        assert!(decode_var_u64(&mut &[0x80, 0x80][..]).is_err());
+        assert!(decode_var_u64(&mut &[0xff; MAX_VAR_U64_LEN][..]).is_err());
+    }
+
+    #[test]
+    fn var_i64_serialize() {
+        for &v in I64_TESTS {
+            let mut buf = vec![];
+            buf.encode_var_i64(v).unwrap();
+            assert!(buf.len() <= MAX_VAR_U64_LEN);
+            let mut data = buf.as_slice();
+            assert_eq!(v, decode_var_i64(&mut data).unwrap());
+            assert!(data.is_empty());
+        }
+
+        for (v, encoded) in [(0, 0), (-1, 1), (1, 2), (-64, 127)] {
+            let mut buf = vec![];
+            buf.encode_var_i64(v).unwrap();
+            assert_eq!(buf, [encoded]);
+        }
+        let mut buf = vec![];
+        buf.encode_var_i64(i64::MIN).unwrap();
+        assert_eq!(buf.len(), MAX_VAR_U64_LEN);
+    }
+
+    #[test]
+    fn var_u64_lengths() {
+        let encoded_len = |v: u64| {
+            let mut buf = vec![];
+            buf.encode_var_u64(v).unwrap();
+            assert_eq!(decode_var_u64(&mut buf.as_slice()).unwrap(), v);
+            buf.len()
+        };
+        assert_eq!(encoded_len(0), 1);
+        assert_eq!(encoded_len(127), 1);
+        assert_eq!(encoded_len(u64::MAX), MAX_VAR_U64_LEN);
+
+        // Each 7-bit step adds a byte.
+        for bits in (7..64).step_by(7) {
+            let n = bits / 7;
+            assert_eq!(encoded_len((1 << bits) - 1), n);
+            assert_eq!(encoded_len(1 << bits), n + 1);
+        }
+    }
+
+    #[test]
+    fn var_u64_non_canonical() {
+        for overlong in [&[0x80, 0x00][..], &[0x81, 0x80, 0x00], &[0xff, 0x00]] {
+            let mut data = overlong;
+            assert!(matches!(
+                decode_var_u64(&mut data),
+                Err(Error::NonCanonicalVarint)
+            ));
+            assert_eq!(data, overlong);
+        }
+
+        // Other varint readers reject them as well.
+        let mut overlong_count = &[0x81, 0x00, 0x01][..];
+        assert!(matches!(
+            crate::codec::bitset::decode_bitset(&mut overlong_count),
+            Err(Error::NonCanonicalVarint)
+        ));
+
+        assert_eq!(decode_var_u64(&mut &[0x00][..]).unwrap(), 0);
+        assert_eq!(decode_var_u64(&mut &[0x80, 0x01][..]).unwrap(), 128);
+    }
 }
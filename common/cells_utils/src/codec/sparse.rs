@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use super::number::{self, Error, NumberEncoder, Result};
+
+/// Writes a field-id to bytes map as a varint count followed by
+/// `(field_id varint, len varint, bytes)` entries.
+///
+/// Entries are written in field-id order, so equal maps always produce the
+/// same bytes.
+pub fn encode_fields(buf: &mut Vec<u8>, fields: &BTreeMap<u16, &[u8]>) -> Result<()> {
+    buf.encode_var_u64(fields.len() as u64)?;
+    for (&id, data) in fields {
+        buf.encode_var_u64(id as u64)?;
+        buf.encode_var_u64(data.len() as u64)?;
+        buf.extend_from_slice(data);
+    }
+    Ok(())
+}
+
+/// Decodes a map written by `encode_fields`, advancing `data` past it.
+pub fn decode_fields(data: &mut &[u8]) -> Result<BTreeMap<u16, Vec<u8>>> {
+    let count = number::decode_var_u64(data)?;
+    let mut fields = BTreeMap::new();
+    for _ in 0..count {
+        let id = number::decode_var_u64(data)?;
+        let id = u16::try_from(id).map_err(|_| Error::EncoderValueOverflow)?;
+        let len = number::decode_var_u64(data)? as usize;
+        if data.len() < len {
+            return Err(Error::EncoderUnexpectedEOF);
+        }
+        let (field, rest) = data.split_at(len);
+        fields.insert(id, field.to_vec());
+        *data = rest;
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn fields_serialize() {
+        let mut fields: BTreeMap<u16, &[u8]> = BTreeMap::new();
+        fields.insert(300, b"name");
+        fields.insert(1, b"");
+        fields.insert(u16::MAX, &[0, 0xff]);
+
+        let mut buf = vec![];
+        encode_fields(&mut buf, &fields).unwrap();
+        buf.push(0xaa);
+
+        let mut data = buf.as_slice();
+        let decoded = decode_fields(&mut data).unwrap();
+        assert_eq!(data, &[0xaa]);
+        assert_eq!(decoded.len(), fields.len());
+        for (id, v) in &fields {
+            assert_eq!(decoded[id], *v);
+        }
+
+        let mut buf = vec![];
+        encode_fields(&mut buf, &BTreeMap::new()).unwrap();
+        assert_eq!(buf, &[0]);
+        assert!(decode_fields(&mut buf.as_slice()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fields_deterministic() {
+        let mut a: BTreeMap<u16, &[u8]> = BTreeMap::new();
+        a.insert(2, b"b");
+        a.insert(1, b"a");
+        let mut b: BTreeMap<u16, &[u8]> = BTreeMap::new();
+        b.insert(1, b"a");
+        b.insert(2, b"b");
+
+        let (mut buf_a, mut buf_b) = (vec![], vec![]);
+        encode_fields(&mut buf_a, &a).unwrap();
+        encode_fields(&mut buf_b, &b).unwrap();
+        assert_eq!(buf_a, buf_b);
+        assert_eq!(buf_a, &[2, 1, 1, b'a', 2, 1, b'b']);
+    }
+
+    #[test]
+    fn fields_truncated() {
+        let mut fields: BTreeMap<u16, &[u8]> = BTreeMap::new();
+        fields.insert(7, b"value");
+        let mut buf = vec![];
+        encode_fields(&mut buf, &fields).unwrap();
+
+        for len in 0..buf.len() {
+            assert!(decode_fields(&mut &buf[..len]).is_err());
+        }
+
+        // Field ids are limited to u16.
+        let mut buf = vec![];
+        buf.encode_var_u64(1).unwrap();
+        buf.encode_var_u64(u16::MAX as u64 + 1).unwrap();
+        buf.encode_var_u64(0).unwrap();
+        assert!(decode_fields(&mut buf.as_slice()).is_err());
+    }
+}
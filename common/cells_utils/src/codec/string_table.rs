@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use super::number::{self, Error, NumberEncoder, Result};
+
+/// A table of distinct strings for dictionary-encoded values, which then only
+/// store indices into the table.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StringTable {
+    strings: Vec<Vec<u8>>,
+    indices: HashMap<Vec<u8>, u32>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of `s`, adding it to the table if it is new.
+    pub fn intern(&mut self, s: &[u8]) -> u32 {
+        if let Some(&idx) = self.indices.get(s) {
+            return idx;
+        }
+        let idx = self.strings.len() as u32;
+        self.strings.push(s.to_vec());
+        self.indices.insert(s.to_vec(), idx);
+        idx
+    }
+
+    pub fn get(&self, idx: u32) -> Option<&[u8]> {
+        self.strings.get(idx as usize).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Writes a varint count followed by varint-length-prefixed strings in
+    /// index order.
+    pub fn encode_table(&self, buf: &mut Vec<u8>) -> Result<()> {
+        buf.encode_var_u64(self.strings.len() as u64)?;
+        for s in &self.strings {
+            buf.encode_var_u64(s.len() as u64)?;
+            buf.extend_from_slice(s);
+        }
+        Ok(())
+    }
+
+    /// Decodes a table written by `encode_table`, advancing `data` past it.
+    ///
+    /// A string listed twice is rejected, since indices past it would
+    /// otherwise resolve to the wrong strings.
+    pub fn decode_table(data: &mut &[u8]) -> Result<StringTable> {
+        let count = number::decode_var_u64(data)?;
+        let mut table = StringTable::new();
+        for index in 0..count as usize {
+            let len = number::decode_var_u64(data)? as usize;
+            if data.len() < len {
+                return Err(Error::EncoderUnexpectedEOF);
+            }
+            let (s, rest) = data.split_at(len);
+            if table.intern(s) as usize != index {
+                return Err(Error::DuplicateEntry { index });
+            }
+            *data = rest;
+        }
+        Ok(table)
+    }
+}
+
+pub fn encode_index(buf: &mut Vec<u8>, idx: u32) -> Result<()> {
+    buf.encode_var_u64(idx as u64)
+}
+
+pub fn decode_index(data: &mut &[u8]) -> Result<u32> {
+    let idx = number::decode_var_u64(data)?;
+    u32::try_from(idx).map_err(|_| Error::EncoderValueOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn intern() {
+        let mut table = StringTable::new();
+        assert!(table.is_empty());
+        assert_eq!(table.intern(b"red"), 0);
+        assert_eq!(table.intern(b"green"), 1);
+        assert_eq!(table.intern(b"red"), 0);
+        assert_eq!(table.intern(b""), 2);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get(1), Some(&b"green"[..]));
+        assert_eq!(table.get(3), None);
+    }
+
+    #[test]
+    fn dictionary_serialize() {
+        let values: &[&[u8]] = &[b"red", b"green", b"red", b"blue", b"green", b"red"];
+
+        let mut table = StringTable::new();
+        let mut indices = vec![];
+        for v in values {
+            encode_index(&mut indices, table.intern(v)).unwrap();
+        }
+        let mut buf = vec![];
+        table.encode_table(&mut buf).unwrap();
+
+        let mut data = buf.as_slice();
+        let decoded = StringTable::decode_table(&mut data).unwrap();
+        assert!(data.is_empty());
+        assert_eq!(decoded, table);
+
+        let mut data = indices.as_slice();
+        for v in values {
+            let idx = decode_index(&mut data).unwrap();
+            assert_eq!(decoded.get(idx), Some(*v));
+        }
+        assert!(data.is_empty());
+
+        assert!(StringTable::decode_table(&mut &buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn decode_table_rejects_duplicates() {
+        // `[b"a", b"b", b"a", b"c"]`
+        let buf = [4, 1, b'a', 1, b'b', 1, b'a', 1, b'c'];
+        assert!(matches!(
+            StringTable::decode_table(&mut &buf[..]),
+            Err(Error::DuplicateEntry { index: 2 })
+        ));
+    }
+}
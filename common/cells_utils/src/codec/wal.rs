@@ -0,0 +1,199 @@
+use super::number::{self, Error, NumberEncoder, Result};
+
+/// Appends a self-framing WAL record:
+/// `seq || key_len || key || value_len || value || crc32c`, with varint
+/// lengths and the CRC-32C covering everything before it.
+pub fn encode_wal_record(seq: u64, key: &[u8], value: &[u8], buf: &mut Vec<u8>) {
+    let start = buf.len();
+    buf.encode_u64(seq).unwrap();
+    buf.encode_var_u64(key.len() as u64).unwrap();
+    buf.extend_from_slice(key);
+    buf.encode_var_u64(value.len() as u64).unwrap();
+    buf.extend_from_slice(value);
+    let crc = crc32c::crc32c(&buf[start..]);
+    buf.encode_u32(crc).unwrap();
+}
+
+/// Replays the records of a WAL as `(seq, key, value)`.
+///
+/// A record that runs past the end of the log, as a crash mid-write leaves
+/// it, ends the iteration cleanly; `remaining` and `offset` tell the caller
+/// what was left behind. A complete record that fails its CRC is yielded as
+/// `ChecksumMismatch`. Either way the reader stops there, since the framing
+/// of anything behind it can't be trusted.
+pub struct WalReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    strict: bool,
+    failed: bool,
+}
+
+impl<'a> WalReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        WalReader {
+            data,
+            offset: 0,
+            strict: false,
+            failed: false,
+        }
+    }
+
+    /// Like `new`, but a torn tail is yielded as `TruncatedRecord` instead of
+    /// ending the iteration, for logs that must end on a record boundary.
+    pub fn strict(data: &'a [u8]) -> Self {
+        WalReader {
+            strict: true,
+            ..WalReader::new(data)
+        }
+    }
+
+    /// The bytes not consumed yet, from the torn tail or the record that
+    /// failed once iteration ends.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The offset of `remaining` in the log.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read_record(&self) -> Result<(u64, &'a [u8], &'a [u8], usize)> {
+        let mut data = self.data;
+        let seq = number::decode_u64(&mut data)?;
+        let key = read_framed(&mut data)?;
+        let value = read_framed(&mut data)?;
+        let len = self.data.len() - data.len();
+        let expected = number::decode_u32(&mut data)?;
+        let actual = crc32c::crc32c(&self.data[..len]);
+        if expected != actual {
+            return Err(Error::ChecksumMismatch { expected, actual });
+        }
+        Ok((seq, key, value, len + number::U32_SIZE))
+    }
+}
+
+fn read_framed<'a>(data: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = number::decode_var_u64(data)?;
+    if (data.len() as u64) < len {
+        return Err(Error::EncoderUnexpectedEOF);
+    }
+    let (bytes, rest) = data.split_at(len as usize);
+    *data = rest;
+    Ok(bytes)
+}
+
+impl<'a> Iterator for WalReader<'a> {
+    type Item = Result<(u64, &'a [u8], &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+        match self.read_record() {
+            Ok((seq, key, value, len)) => {
+                self.data = &self.data[len..];
+                self.offset += len;
+                Some(Ok((seq, key, value)))
+            }
+            Err(e) => {
+                self.failed = true;
+                match e {
+                    Error::EncoderUnexpectedEOF if !self.strict => None,
+                    Error::EncoderUnexpectedEOF => Some(Err(Error::TruncatedRecord {
+                        offset: self.offset,
+                    })),
+                    e => Some(Err(e)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn log(records: &[(u64, &[u8], &[u8])]) -> Vec<u8> {
+        let mut buf = vec![];
+        for &(seq, key, value) in records {
+            encode_wal_record(seq, key, value, &mut buf);
+        }
+        buf
+    }
+
+    const RECORDS: &[(u64, &[u8], &[u8])] = &[
+        (1, b"a", b"value"),
+        (2, b"", b""),
+        (3, b"\x00key\xff", &[7; 300]),
+    ];
+
+    #[test]
+    fn wal_replay() {
+        let buf = log(RECORDS);
+        let replayed: Vec<_> = WalReader::new(&buf).map(Result::unwrap).collect();
+        assert_eq!(replayed, RECORDS);
+        assert_eq!(WalReader::new(&[]).count(), 0);
+    }
+
+    #[test]
+    fn wal_torn_tail() {
+        let buf = log(RECORDS);
+        let complete = log(&RECORDS[..2]).len();
+        for len in complete..buf.len() {
+            let mut reader = WalReader::new(&buf[..len]);
+            let replayed: Result<Vec<_>> = reader.by_ref().collect();
+            assert_eq!(replayed.unwrap(), &RECORDS[..2]);
+            assert_eq!(reader.remaining(), &buf[complete..len]);
+            assert_eq!(reader.offset(), complete);
+
+            let mut reader = WalReader::strict(&buf[..len]);
+            let replayed: Vec<_> = reader.by_ref().take(2).map(Result::unwrap).collect();
+            assert_eq!(replayed, &RECORDS[..2]);
+            if len == complete {
+                assert!(reader.next().is_none());
+            } else {
+                assert!(matches!(
+                    reader.next(),
+                    Some(Err(Error::TruncatedRecord { offset })) if offset == complete
+                ));
+                assert!(reader.next().is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn wal_corrupt_length() {
+        let mut buf = log(RECORDS);
+        let first = log(&RECORDS[..1]).len();
+        // The key_len of the second record, 0, now runs past the log.
+        buf[first + number::U64_SIZE] = 0xff;
+        buf.insert(first + number::U64_SIZE + 1, 0x7f);
+        let mut reader = WalReader::new(&buf);
+        assert_eq!(reader.next().unwrap().unwrap(), RECORDS[0]);
+        assert!(reader.next().is_none());
+        assert_eq!(reader.offset(), first);
+
+        let mut reader = WalReader::strict(&buf);
+        assert_eq!(reader.next().unwrap().unwrap(), RECORDS[0]);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::TruncatedRecord { offset })) if offset == first
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn wal_corrupt_record() {
+        let mut buf = log(RECORDS);
+        // A flipped bit in the value of the first record.
+        buf[11] ^= 0x01;
+        let mut reader = WalReader::new(&buf);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::ChecksumMismatch { .. }))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
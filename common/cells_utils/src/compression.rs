@@ -0,0 +1,72 @@
+/// Values shorter than this are never worth compressing.
+pub const MIN_COMPRESSIBLE_LEN: usize = 64;
+
+const SAMPLE_LEN: usize = 4096;
+// Fraction of the maximal entropy above which a sample is treated as noise.
+const ENTROPY_THRESHOLD: f64 = 0.9;
+
+/// Guesses whether compressing `data` would pay off, from the byte entropy of
+/// a prefix sample.
+///
+/// Already-compressed or encrypted payloads look uniformly random and are
+/// rejected. Data that is repetitive but evenly spread over all byte values
+/// (e.g. a repeated `0..=255` ramp) is a false negative.
+pub fn is_likely_compressible(data: &[u8]) -> bool {
+    if data.len() < MIN_COMPRESSIBLE_LEN {
+        return false;
+    }
+
+    let sample = &data[..data.len().min(SAMPLE_LEN)];
+    let mut freq = [0usize; 256];
+    for &b in sample {
+        freq[b as usize] += 1;
+    }
+
+    let n = sample.len() as f64;
+    let entropy: f64 = freq
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            -p * p.log2()
+        })
+        .sum();
+    let max_entropy = n.min(256.0).log2();
+
+    entropy < max_entropy * ENTROPY_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                (x >> 32) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compressible() {
+        assert!(is_likely_compressible(&b"abcabcabd".repeat(100)));
+        assert!(is_likely_compressible(&[0; 1024]));
+        assert!(is_likely_compressible(
+            &br#"{"id":1,"name":"cells","tags":["kv","db"]}"#.repeat(20)
+        ));
+    }
+
+    #[test]
+    fn incompressible() {
+        assert!(!is_likely_compressible(&random_bytes(64)));
+        assert!(!is_likely_compressible(&random_bytes(10_000)));
+        assert!(!is_likely_compressible(&[0; MIN_COMPRESSIBLE_LEN - 1]));
+        assert!(!is_likely_compressible(b""));
+    }
+}
@@ -13,6 +13,9 @@
 // limitations under the License.
 
 pub mod codec;
+pub mod compression;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,30 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// A global allocator counting the allocations made by each thread, so
+/// tests running in parallel don't disturb each other.
+///
+/// Install it in a test module with `#[global_allocator]` and read the count
+/// of the current thread with `allocs`.
+pub struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// The number of allocations the current thread made through
+/// `CountingAlloc`.
+pub fn allocs() -> usize {
+    ALLOCS.with(Cell::get)
+}